@@ -0,0 +1,210 @@
+use rodio::Decoder;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use syllabs::{Directory, FileSource, Folding, SyllablesTree, find_audio_files};
+use syllabs::voice::{load_voice, read_manifest, VoiceError, MANIFEST_FILE};
+use syllabs::wav::Wav;
+
+use crate::layout::KeyboardLayout;
+
+/// Problems found in a voice directory. Errors make the voice unusable in places, warnings
+/// are only worth a look.
+#[derive(Default)]
+pub struct CheckReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>
+}
+
+pub fn check_voice(voice_dir: &Path, layout: &KeyboardLayout) -> Result<CheckReport, VoiceError> {
+    let source = Directory(voice_dir.to_path_buf());
+    let root = Path::new("");
+    let files = find_audio_files(root, &source)?;
+    let mut report = CheckReport::default();
+    let mut formats: BTreeMap<String, usize> = BTreeMap::new();
+
+    for (_, path) in files.iter() {
+        match source.read(path) {
+            Ok(bytes) => match Wav::parse(&bytes) {
+                Ok(wav) => {
+                    if wav.data.is_empty() {
+                        report.errors.push(format!("{}: contains no samples", path.display()));
+                    }
+
+                    let format = format!("{} Hz, {} channel(s), {} bits", wav.format.sample_rate, wav.format.channels, wav.format.bits_per_sample);
+                    *formats.entry(format).or_default() += 1;
+
+                    let samples = wav.data.len() / usize::from(wav.format.bits_per_sample / 8);
+                    if let Err(err) = decode(&bytes, samples) {
+                        report.errors.push(format!("{}: {}", path.display(), err));
+                    }
+                },
+                Err(err) => report.errors.push(format!("{}: {}", path.display(), err))
+            },
+            Err(err) => report.errors.push(format!("{}: {}", path.display(), err))
+        }
+    }
+
+    if formats.len() > 1 {
+        let counts: Vec<String> = formats.iter().map(|(format, count)| format!("{} file(s) in {}", count, format)).collect();
+        report.warnings.push(format!("recordings use different formats: {}", counts.join(", ")));
+    }
+
+    let mut stems: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+    for (file_stem, path) in files.iter() {
+        stems.entry(file_stem.to_lowercase()).or_default().push(path);
+    }
+
+    for paths in stems.values().filter(|paths| paths.len() > 1) {
+        let names: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+        report.errors.push(format!("{} differ only in case, only one of them is loaded", names.join(", ")));
+    }
+
+    match read_manifest(root, &source) {
+        Ok(manifest) => {
+            for file in manifest.audio.keys() {
+                if !files.iter().any(|(_, path)| path == Path::new(file)) {
+                    report.errors.push(format!("{}: listed in {} but not found", file, MANIFEST_FILE));
+                }
+            }
+
+            for (_, path) in files.iter() {
+                if !manifest.audio.keys().any(|file| path == Path::new(file)) {
+                    report.warnings.push(format!("{}: not listed in {}", path.display(), MANIFEST_FILE));
+                }
+            }
+        },
+        Err(err) if err.is_not_found() => {},
+        Err(err) => return Err(err)
+    }
+
+    // The syllables the game loads, from the manifest or with load_folder without one
+    let syllabs_tree: SyllablesTree<()> = load_voice(root, &source, &|_: &Path| (), Folding::default())?;
+
+    let typeable = layout.typeable_chars();
+    for (spelling, _) in syllabs_tree.syllables() {
+        let untypeable: String = spelling.chars().filter(|c| !typeable.contains(c)).collect();
+        if !untypeable.is_empty() {
+            report.errors.push(format!("'{}': '{}' cannot be typed with the {} layout", spelling, untypeable, layout.name));
+        }
+    }
+
+    for prefix in syllabs_tree.silent_prefixes() {
+        match prefix.chars().last().filter(|_| syllabs_tree.get(prefix.clone()).is_some()) {
+            Some(last_char) => report.warnings.push(format!("'{}' has no audio, its last letter '{}' is played instead", prefix, last_char)),
+            None => report.warnings.push(format!("'{}' has no audio, nothing is played", prefix))
+        }
+    }
+
+    Ok(report)
+}
+
+/// Decode `bytes` with rodio, as the game does to play them, expecting `samples` samples.
+fn decode(bytes: &[u8], samples: usize) -> Result<(), String> {
+    let decoder = Decoder::new(Cursor::new(bytes.to_vec())).map_err(|err| format!("cannot be decoded: {}", err))?;
+    let decoded = decoder.count();
+    if decoded < samples {
+        return Err(format!("only {} of {} samples could be decoded", decoded, samples));
+    }
+    Ok(())
+}
+
+/// Check `voice_dir` against the keyboard layout `layout_name` of the `assets` folder, both
+/// relative to the current directory, and print the problems found. Fails if there are errors,
+/// or warnings when `strict` is set.
+pub fn run(voice_dir: &Path, assets: &Path, layout_name: &str, strict: bool) -> ExitCode {
+    match env::current_dir() {
+        Ok(current_dir) => run_from(&current_dir, voice_dir, assets, layout_name, strict),
+        Err(err) => {
+            eprintln!("error: current directory: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `run` with relative paths resolved against `dir`.
+fn run_from(dir: &Path, voice_dir: &Path, assets: &Path, layout_name: &str, strict: bool) -> ExitCode {
+    let layout = match KeyboardLayout::load(layout_name, &Directory(dir.join(assets))) {
+        Ok(layout) => layout,
+        Err(err) => {
+            eprintln!("error: layout '{}': {}", layout_name, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let voice_dir = dir.join(voice_dir);
+    let voice_dir = match voice_dir.canonicalize() {
+        Ok(voice_dir) => voice_dir,
+        Err(err) => {
+            eprintln!("error: {}: {}", voice_dir.display(), err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("Checking voice '{}'", voice_dir.display());
+
+    match check_voice(&voice_dir, &layout) {
+        Ok(report) => {
+            for error in report.errors.iter() {
+                println!("error: {}", error);
+            }
+            for warning in report.warnings.iter() {
+                println!("warning: {}", warning);
+            }
+            println!("{} error(s), {} warning(s)", report.errors.len(), report.warnings.len());
+
+            if report.errors.is_empty() && (!strict || report.warnings.is_empty()) {
+                ExitCode::SUCCESS
+            }
+            else {
+                ExitCode::FAILURE
+            }
+        },
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn paths_are_relative_to_the_current_directory() {
+        let dir = env::temp_dir().join(format!("syllabs-check-{}", std::process::id()));
+        fs::create_dir_all(dir.join("assets").join("layouts")).unwrap();
+        fs::create_dir_all(dir.join("assets").join("audio").join("fr")).unwrap();
+        fs::write(dir.join("assets").join("layouts").join("test.toml"), "name = \"Test\"\n").unwrap();
+
+        let voice_dir = Path::new("assets/audio/fr");
+        let from_dir = run_from(&dir, voice_dir, Path::new("assets"), "test", true);
+        let from_elsewhere = run_from(&dir.join("assets"), voice_dir, Path::new("assets"), "test", true);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(from_dir, ExitCode::SUCCESS);
+        assert_eq!(from_elsewhere, ExitCode::FAILURE);
+    }
+
+    #[test]
+    fn silent_prefixes_tell_what_is_played() {
+        let dir = env::temp_dir().join(format!("syllabs-silent-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE), "[voice]\nlanguage = \"fr\"\nspeaker = \"Test\"\n\n[audio]\n\"pra.wav\" = \"pra\"\n\"r.wav\" = \"r\"\n").unwrap();
+
+        let report = check_voice(&dir, &KeyboardLayout::default());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.unwrap().warnings, [
+            "'p' has no audio, nothing is played",
+            "'pr' has no audio, its last letter 'r' is played instead"
+        ]);
+    }
+}
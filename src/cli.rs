@@ -0,0 +1,138 @@
+use bevy::prelude::Color;
+use clap::{Parser, Subcommand};
+
+use std::path::PathBuf;
+
+use crate::plugins::{AccessibilityPreset, DisplayMode, GameMode, InputMethod};
+
+/// Type letters, hear syllables.
+#[derive(Parser)]
+#[command(name = "syllabs-rs", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Folder holding the fonts, the voices and the keyboard layouts
+    #[arg(long, global = true, value_name = "DIR", default_value = "assets")]
+    pub assets: String,
+
+    /// Player whose progress is loaded and saved, one file per profile in the user's data directory. Chosen in
+    /// the menus when not given
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Voice pack to play syllables with, a directory of <ASSETS>/audio
+    #[arg(long, value_name = "NAME")]
+    pub voice: Option<String>,
+
+    /// Pick the first voice pack of this language when no voice is given
+    #[arg(long, value_name = "LANG")]
+    pub language: Option<String>,
+
+    /// Window width in pixels
+    #[arg(long, default_value_t = 1280.)]
+    pub width: f32,
+
+    /// Window height in pixels
+    #[arg(long, default_value_t = 720.)]
+    pub height: f32,
+
+    /// Run in fullscreen instead of a window
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// How the letters are written
+    #[arg(long, value_enum, default_value_t = DisplayMode::Lowercase)]
+    pub display: DisplayMode,
+
+    /// Colours, font and spacing of the letters
+    #[arg(long, value_enum, default_value_t = AccessibilityPreset::Standard)]
+    pub preset: AccessibilityPreset,
+
+    /// Colour of the vowels, as RRGGBB, instead of the one of the preset
+    #[arg(long, value_name = "HEX", value_parser = parse_color)]
+    pub vowel_color: Option<Color>,
+
+    /// Colour of the consonants, as RRGGBB, instead of the one of the preset
+    #[arg(long, value_name = "HEX", value_parser = parse_color)]
+    pub consonant_color: Option<Color>,
+
+    /// Turn off every animation, for the players they distract
+    #[arg(long)]
+    pub calm: bool,
+
+    /// How long a typed letter bounces in, in milliseconds, 0 to turn it off
+    #[arg(long, value_name = "MS", default_value_t = 350)]
+    pub bounce: u64,
+
+    /// How long the letters pulse when a syllable is complete or an answer right, in milliseconds, 0 to turn
+    /// it off
+    #[arg(long, value_name = "MS", default_value_t = 400)]
+    pub pulse: u64,
+
+    /// How long the letters shake on a wrong answer, in milliseconds, 0 to turn it off
+    #[arg(long, value_name = "MS", default_value_t = 400)]
+    pub shake: u64,
+
+    /// How long confetti falls, in milliseconds, 0 to turn it off
+    #[arg(long, value_name = "MS", default_value_t = 1500)]
+    pub confetti: u64,
+
+    /// Throw confetti after this many right answers in a row, 0 to turn it off
+    #[arg(long, value_name = "ANSWERS", default_value_t = 5)]
+    pub confetti_streak: u32,
+
+    /// How typed letters are read
+    #[arg(long, value_enum, default_value_t = InputMethod::Text)]
+    pub input: InputMethod,
+
+    /// Keyboard layout used to type accented letters with `--input keys`, a file of <ASSETS>/layouts: azerty,
+    /// qwerty, qwertz or bepo
+    #[arg(long, global = true, default_value = "azerty")]
+    pub layout: String,
+
+    /// Ignore the keys while more than this many are pressed within --mash-window, 0 to allow
+    /// key-mashing
+    #[arg(long, value_name = "PRESSES", default_value_t = 0)]
+    pub mash_limit: u32,
+
+    /// Window key-mashing is counted over, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    pub mash_window: u64,
+
+    /// Accept plain vowels for accented ones, for beginners
+    #[arg(long)]
+    pub ignore_accents: bool,
+
+    /// Start playing this game mode right away instead of showing the menus
+    #[arg(long, value_enum)]
+    pub mode: Option<GameMode>,
+
+    /// Words to type with `--mode words`, a file of <ASSETS>/words
+    #[arg(long, value_name = "NAME", default_value = "fr")]
+    pub words: String
+}
+
+/// A colour written as RRGGBB or RRGGBBAA, with or without a leading '#'.
+fn parse_color(hex: &str) -> Result<Color, String> {
+    Color::hex(hex.trim_start_matches('#')).map_err(|err| err.to_string())
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Report problems in a voice directory without opening a window
+    Check {
+        /// Voice directory to check, for example assets/audio/fr
+        voice_dir: PathBuf,
+
+        /// Also fail when there are warnings
+        #[arg(long)]
+        strict: bool
+    },
+    /// Write the progress of a profile, given with --profile, as CSV and HTML
+    Report {
+        /// Directory to write <PROFILE>-report.csv and <PROFILE>-report.html in
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        output: PathBuf
+    }
+}
@@ -1,10 +1,19 @@
 use bevy::input::keyboard::KeyCode;
 
+/// Keys that `str_from_key` turns into a character.
+pub const TYPEABLE_KEYS: [KeyCode; 36] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9
+];
+
+/// Letters and digits, which every layout reports with the same key codes. Accented letters
+/// depend on the layout, see `KeyboardLayout`.
 pub fn str_from_key(key_code: KeyCode) -> Option<&'static str> {
     match key_code {
-        KeyCode::Key2 => Some("é"),
-        KeyCode::Key7 => Some("è"),
-        KeyCode::Key9 => Some("ç"),
         KeyCode::A => Some("A"),
         KeyCode::B => Some("B"),
         KeyCode::C => Some("C"),
@@ -0,0 +1,48 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Level exercises start at, two-letter syllables such as "ba".
+pub const START_LEVEL: u32 = 2;
+
+/// Hardest level exercises grow to.
+pub const MAX_LEVEL: u32 = 8;
+
+/// Whether `c` is a vowel, accented or not.
+pub fn is_vowel(c: char) -> bool {
+    matches!(c.nfd().next(), Some('a' | 'e' | 'i' | 'o' | 'u' | 'y'))
+}
+
+/// How hard a syllable is to hear or read: one point per letter, plus two per consonant
+/// following another one, as in "tr" or "bl".
+pub fn difficulty(syllable: &str) -> u32 {
+    let chars: Vec<char> = syllable.nfc().collect();
+    let clusters = chars.windows(2)
+        .filter(|pair| !is_vowel(pair[0]) && !is_vowel(pair[1]))
+        .count();
+
+    (chars.len() + 2 * clusters) as u32
+}
+
+/// Chance weight of a syllable of `difficulty` at `level`, halved for every point away from it.
+pub fn level_weight(difficulty: u32, level: u32) -> f32 {
+    0.5f32.powi(difficulty.abs_diff(level) as i32)
+}
+
+/// Pick one of `items` at random, each with a chance proportional to its weight.
+pub fn pick_weighted<T, F: Fn(&T) -> f32>(items: &[T], weight: F) -> Option<&T> {
+    let weights: Vec<f32> = items.iter().map(|item| weight(item).max(0.0)).collect();
+    let total: f32 = weights.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut target = fastrand::f32() * total;
+    for (item, weight) in items.iter().zip(weights.iter()) {
+        if target < *weight {
+            return Some(item);
+        }
+        target -= weight;
+    }
+
+    // Rounding can leave `target` just above the last weight
+    items.iter().zip(weights.iter()).rev().find(|(_, weight)| **weight > 0.0).map(|(item, _)| item)
+}
@@ -0,0 +1,142 @@
+use bevy::input::keyboard::KeyCode;
+use serde::Deserialize;
+use syllabs::FileSource;
+
+use core::fmt;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::path::Path;
+
+use crate::defs::{key_from_str, str_from_key, TYPEABLE_KEYS};
+
+/// Asset folder holding one `<name>.toml` file per keyboard layout.
+pub const LAYOUTS_FOLDER: &str = "layouts";
+
+/// Content of a layout file.
+///
+/// ```toml
+/// name = "AZERTY"
+///
+/// [keys]
+/// Key2 = "é"
+///
+/// [scancodes]
+/// 3 = "é"
+/// ```
+///
+/// `keys` are `KeyCode` names as understood by `key_from_str`. Some systems report no key code
+/// for accented keys, `scancodes` then matches the physical key instead. Scan codes are the ones
+/// of Linux (evdev) and Windows (set 1), which agree on these keys; macOS numbers keys
+/// differently and needs `keys`.
+#[derive(Deserialize)]
+struct LayoutFile {
+    name: String,
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    #[serde(default)]
+    scancodes: BTreeMap<String, String>
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    UnknownKey(String),
+    InvalidScanCode(String),
+    NotACharacter(String)
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Io(err) => write!(f, "{}", err),
+            LayoutError::Parse(err) => write!(f, "invalid layout file: {}", err),
+            LayoutError::UnknownKey(key) => write!(f, "unknown key '{}'", key),
+            LayoutError::InvalidScanCode(scan_code) => write!(f, "invalid scan code '{}'", scan_code),
+            LayoutError::NotACharacter(value) => write!(f, "'{}' is not a single character", value)
+        }
+    }
+}
+
+impl From<io::Error> for LayoutError {
+    fn from(err: io::Error) -> Self {
+        LayoutError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for LayoutError {
+    fn from(err: toml::de::Error) -> Self {
+        LayoutError::Parse(err)
+    }
+}
+
+fn parse_char(value: &str) -> Result<char, LayoutError> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(LayoutError::NotACharacter(value.to_string()))
+    }
+}
+
+/// Characters typed by the keys that differ between keyboard layouts, on top of the letters
+/// of `str_from_key`.
+#[derive(Default)]
+pub struct KeyboardLayout {
+    pub name: String,
+    keys: HashMap<KeyCode, char>,
+    scancodes: HashMap<u32, char>
+}
+
+impl KeyboardLayout {
+    /// Load `<LAYOUTS_FOLDER>/<name>.toml`.
+    pub fn load(name: &str, source: &(impl FileSource + ?Sized)) -> Result<KeyboardLayout, LayoutError> {
+        let path = Path::new(LAYOUTS_FOLDER).join(format!("{}.toml", name));
+        let bytes = source.read(&path)?;
+        let layout_file: LayoutFile = toml::from_slice(&bytes)?;
+
+        let mut keys = HashMap::new();
+        for (key, value) in layout_file.keys.iter() {
+            let key_code = key_from_str(key).map_err(|_| LayoutError::UnknownKey(key.clone()))?;
+            keys.insert(key_code, parse_char(value)?);
+        }
+
+        let mut scancodes = HashMap::new();
+        for (scan_code, value) in layout_file.scancodes.iter() {
+            let scan_code_value = scan_code.parse().map_err(|_| LayoutError::InvalidScanCode(scan_code.clone()))?;
+            scancodes.insert(scan_code_value, parse_char(value)?);
+        }
+
+        Ok(KeyboardLayout {
+            name: layout_file.name,
+            keys,
+            scancodes
+        })
+    }
+
+    /// Lowercase character typed by a key press, if any. Scan codes are only looked up when the
+    /// system reports no key code, as they differ between platforms.
+    pub fn char_from_key(&self, key_code: Option<KeyCode>, scan_code: u32) -> Option<char> {
+        let key_code = match key_code {
+            Some(key_code) => key_code,
+            None => return self.scancodes.get(&scan_code).copied()
+        };
+
+        if let Some(c) = self.keys.get(&key_code) {
+            return Some(*c);
+        }
+
+        str_from_key(key_code)
+            .map(|str| str.to_lowercase())
+            .and_then(|str| str.chars().next())
+    }
+
+    /// Every lowercase character the layout can type.
+    pub fn typeable_chars(&self) -> HashSet<char> {
+        TYPEABLE_KEYS.iter()
+            .filter_map(|key_code| str_from_key(*key_code))
+            .flat_map(|str| str.to_lowercase().chars().collect::<Vec<_>>())
+            .chain(self.keys.values().copied())
+            .chain(self.scancodes.values().copied())
+            .collect()
+    }
+}
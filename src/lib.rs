@@ -0,0 +1,14 @@
+//! Syllables trie, segmentation and voice-pack loading, independent of how the recordings are
+//! played.
+//!
+//! Voice packs are read through a `FileSource`. Reading them with Bevy's asset server needs the
+//! `bevy` feature, on by default.
+
+pub mod source;
+pub mod tree;
+pub mod voice;
+pub mod wav;
+
+pub use source::{Directory, FileSource};
+pub use tree::{AudioClip, Folding, Segment, SyllableAudio, SyllablesTree};
+pub use voice::{find_audio_files, load_folder, AudioLoader};
@@ -0,0 +1,352 @@
+use bevy::prelude::*;
+
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use crate::plugins::{in_game, GameContext, InGameUi, Theme};
+use crate::plugins::text::{SyllableText, TextUpdate};
+
+/// Growth of the text at the height of a pulse.
+const PULSE_SCALE: f32 = 0.15;
+
+/// Half-swings of the text in a shake.
+const SHAKE_SWINGS: f32 = 6.0;
+
+/// Largest move of the text in a shake, relative to the font size.
+const SHAKE_AMPLITUDE: f32 = 0.08;
+
+/// Smallest size of a bouncing letter, relative to the font size, as a letter cannot be drawn at size 0.
+const MIN_BOUNCE_SCALE: f32 = 0.05;
+
+const CONFETTI_COUNT: usize = 60;
+
+/// Side of a piece of confetti in pixels.
+const CONFETTI_SIZE: f32 = 12.0;
+
+/// Pixels per second the confetti speeds down.
+const CONFETTI_GRAVITY: f32 = 900.0;
+
+/// How long each effect lasts, any of them turned off when zero.
+pub struct AnimationSettings {
+    /// No effect at all, for the players they distract or upset.
+    pub calm: bool,
+    /// A typed letter growing into place.
+    pub bounce: Duration,
+    /// The text swelling when a syllable is complete or an answer right.
+    pub pulse: Duration,
+    /// The text shaking on a wrong answer.
+    pub shake: Duration,
+    /// Confetti falling after `confetti_streak` right answers in a row.
+    pub confetti: Duration,
+    /// Right answers in a row that throw confetti, never when 0.
+    pub confetti_streak: u32
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        AnimationSettings {
+            calm: false,
+            bounce: Duration::from_millis(350),
+            pulse: Duration::from_millis(400),
+            shake: Duration::from_millis(400),
+            confetti: Duration::from_millis(1500),
+            confetti_streak: 5
+        }
+    }
+}
+
+/// An animation of the syllable text.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Effect {
+    /// The last letter was typed.
+    Bounce,
+    /// A syllable was completed or an answer was right.
+    Pulse,
+    /// An answer was wrong.
+    Shake
+}
+
+/// What the game context looked like when last animated.
+#[derive(Default)]
+struct Seen {
+    syllable: String,
+    completed: usize,
+    feedback: Option<bool>
+}
+
+impl Seen {
+    fn new(game_ctx: &GameContext) -> Seen {
+        Seen {
+            syllable: game_ctx.syllable.clone(),
+            completed: game_ctx.completed,
+            feedback: game_ctx.feedback
+        }
+    }
+
+    /// Effects telling the player what changed from this to `game_ctx`.
+    fn effects(&self, game_ctx: &GameContext) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        // Typing a letter adds it, or starts anew from it after a miss
+        if !game_ctx.syllable.is_empty() && game_ctx.syllable != self.syllable && !self.syllable.starts_with(&game_ctx.syllable) {
+            effects.push(Effect::Bounce);
+        }
+        if game_ctx.completed > self.completed || (game_ctx.feedback == Some(true) && self.feedback != Some(true)) {
+            effects.push(Effect::Pulse);
+        }
+        if game_ctx.feedback == Some(false) && self.feedback != Some(false) {
+            effects.push(Effect::Shake);
+        }
+        effects
+    }
+}
+
+/// Effects under way, each with the timer of its progress.
+#[derive(Default)]
+struct Animations {
+    seen: Seen,
+    /// Right answers in a row.
+    streak: u32,
+    bounce: Option<Timer>,
+    pulse: Option<Timer>,
+    shake: Option<Timer>
+}
+
+/// A piece of confetti, falling from where it was thrown.
+#[derive(Component)]
+struct Confetti {
+    /// Top left corner in pixels from the top left of the window.
+    position: Vec2,
+    /// Pixels per second, downwards when positive.
+    velocity: Vec2,
+    timer: Timer
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnimationSettings>()
+           .init_resource::<Animations>()
+           .add_system(trigger_system.with_run_criteria(in_game))
+           .add_system(syllable_animation_system.with_run_criteria(in_game).after(trigger_system).after(TextUpdate))
+           .add_system(confetti_system);
+    }
+}
+
+/// Start of a timer running for `duration`, none when the effect is turned off.
+fn start(duration: Duration) -> Option<Timer> {
+    if duration.is_zero() { None } else { Some(Timer::new(duration, false)) }
+}
+
+/// Scale of a letter bouncing in, at `t` from 0 to 1: growing from nothing past its size, then settling.
+fn bounce_scale(t: f32) -> f32 {
+    (1.0 - (1.0 - t).powi(2) * (3.0 * PI * t).cos()).max(MIN_BOUNCE_SCALE)
+}
+
+/// Scale of the text swelling and back, at `t` from 0 to 1.
+fn pulse_scale(t: f32) -> f32 {
+    1.0 + PULSE_SCALE * (PI * t).sin()
+}
+
+/// Move of the text to the right, relative to its amplitude, at `t` from 0 to 1: swinging less and less.
+fn shake_offset(t: f32) -> f32 {
+    (1.0 - t) * (SHAKE_SWINGS * PI * t).sin()
+}
+
+/// Start the effects of what changed in the game context.
+fn trigger_system(
+    mut commands: Commands,
+    settings: Res<AnimationSettings>,
+    game_ctx: Res<GameContext>,
+    theme: Res<Theme>,
+    windows: Res<Windows>,
+    mut animations: ResMut<Animations>) {
+    if !game_ctx.is_changed() {
+        return;
+    }
+
+    let effects = animations.seen.effects(&game_ctx);
+    let answered = game_ctx.feedback.filter(|_| game_ctx.feedback != animations.seen.feedback);
+    animations.seen = Seen::new(&game_ctx);
+    match answered {
+        Some(true) => animations.streak += 1,
+        Some(false) => animations.streak = 0,
+        None => ()
+    }
+
+    if settings.calm {
+        return;
+    }
+
+    for effect in effects {
+        match effect {
+            Effect::Bounce => animations.bounce = start(settings.bounce),
+            Effect::Pulse => animations.pulse = start(settings.pulse),
+            Effect::Shake => animations.shake = start(settings.shake)
+        }
+    }
+
+    let confetti = answered == Some(true)
+        && settings.confetti_streak > 0
+        && animations.streak % settings.confetti_streak == 0
+        && !settings.confetti.is_zero();
+    if !confetti {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary() {
+        info!("{} right answers in a row", animations.streak);
+        throw_confetti(&mut commands, &theme, Vec2::new(window.width(), window.height()), settings.confetti);
+    }
+}
+
+/// Throw pieces of confetti in the colours of `theme` up from the centre of a `window_size` window.
+fn throw_confetti(commands: &mut Commands, theme: &Theme, window_size: Vec2, duration: Duration) {
+    let colors = [theme.letters.vowel, theme.letters.consonant, theme.right];
+    for i in 0..CONFETTI_COUNT {
+        // Upwards, spreading a quarter turn to each side
+        let angle = (fastrand::f32() - 0.5) * PI;
+        let speed = window_size.y * (0.8 + fastrand::f32() * 0.8);
+        let position = window_size / 2.0;
+
+        commands.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(CONFETTI_SIZE), Val::Px(CONFETTI_SIZE)),
+                    position_type: PositionType::Absolute,
+                    position: UiRect { left: Val::Px(position.x), top: Val::Px(position.y), ..default() },
+                    ..default()
+                },
+                color: colors[i % colors.len()].into(),
+                ..default()
+            })
+            .insert(Confetti {
+                position,
+                velocity: Vec2::new(angle.sin(), -angle.cos()) * speed,
+                timer: Timer::new(duration, false)
+            })
+            .insert(InGameUi(None));
+    }
+}
+
+/// Play the effects under way on the syllable text, then leave it as it was.
+fn syllable_animation_system(
+    time: Res<Time>,
+    mut animations: ResMut<Animations>,
+    mut query: Query<(&SyllableText, &mut Text, &mut Transform, &mut Style)>) {
+    let animations = &mut *animations;
+    let bounce = progress(&mut animations.bounce, time.delta());
+    let pulse = progress(&mut animations.pulse, time.delta());
+    let shake = progress(&mut animations.shake, time.delta());
+
+    for (syllable_text, mut text, mut transform, mut style) in query.iter_mut() {
+        if let Some(t) = bounce {
+            let size = syllable_text.font_size * bounce_scale(t);
+            // The last letter of every line
+            let last_letters: Vec<usize> = (0..text.sections.len())
+                .filter(|i| text.sections.get(i + 1).is_none_or(|next| next.value == "\n"))
+                .collect();
+            for i in last_letters {
+                text.sections[i].style.font_size = size;
+            }
+        }
+
+        if let Some(t) = pulse {
+            transform.scale = Vec3::splat(pulse_scale(t));
+        }
+
+        if let Some(t) = shake {
+            style.position.left = Val::Px(shake_offset(t) * SHAKE_AMPLITUDE * syllable_text.font_size);
+        }
+    }
+}
+
+/// Tick `timer` by `delta` and tell how far along it is, from 0 to 1. It is dropped once it
+/// reached 1, after being told so.
+fn progress(timer: &mut Option<Timer>, delta: Duration) -> Option<f32> {
+    let t = timer.as_mut()?.tick(delta).percent();
+    if t >= 1.0 {
+        *timer = None;
+    }
+    Some(t)
+}
+
+fn confetti_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Confetti, &mut Style)>) {
+    for (entity, mut confetti, mut style) in query.iter_mut() {
+        if confetti.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let delta = time.delta_seconds();
+        confetti.velocity.y += CONFETTI_GRAVITY * delta;
+        let velocity = confetti.velocity;
+        confetti.position += velocity * delta;
+        style.position.left = Val::Px(confetti.position.x);
+        style.position.top = Val::Px(confetti.position.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_ctx(syllable: &str, completed: usize, feedback: Option<bool>) -> GameContext {
+        GameContext {
+            syllable: syllable.to_string(),
+            syllables: vec![syllable.to_string()],
+            completed,
+            feedback
+        }
+    }
+
+    fn effects(before: &GameContext, after: &GameContext) -> Vec<Effect> {
+        Seen::new(before).effects(after)
+    }
+
+    #[test]
+    fn typed_letters_bounce() {
+        assert_eq!(effects(&game_ctx("b", 0, None), &game_ctx("ba", 0, None)), [Effect::Bounce]);
+        assert_eq!(effects(&game_ctx("bat", 0, None), &game_ctx("x", 0, None)), [Effect::Bounce]);
+        assert_eq!(effects(&game_ctx("e", 0, None), &game_ctx("é", 0, None)), [Effect::Bounce]);
+        assert!(effects(&game_ctx("ba", 0, None), &game_ctx("", 0, None)).is_empty());
+        assert!(effects(&game_ctx("bat", 0, None), &game_ctx("ba", 0, None)).is_empty());
+    }
+
+    #[test]
+    fn completed_syllables_and_right_answers_pulse() {
+        assert_eq!(effects(&game_ctx("b", 0, None), &game_ctx("ba", 1, None)), [Effect::Bounce, Effect::Pulse]);
+        assert_eq!(effects(&game_ctx("ba", 0, None), &game_ctx("ba", 0, Some(true))), [Effect::Pulse]);
+        assert!(effects(&game_ctx("ba", 1, None), &game_ctx("ba", 1, None)).is_empty());
+    }
+
+    #[test]
+    fn wrong_answers_shake_once() {
+        assert_eq!(effects(&game_ctx("ba", 0, None), &game_ctx("ba", 0, Some(false))), [Effect::Shake]);
+        assert!(effects(&game_ctx("ba", 0, Some(false)), &game_ctx("ba", 0, Some(false))).is_empty());
+    }
+
+    #[test]
+    fn effects_end_where_they_started() {
+        assert!(bounce_scale(0.0) < 0.1);
+        assert!((0..10).map(|i| bounce_scale(i as f32 / 10.0)).any(|scale| scale > 1.0));
+        assert_eq!(bounce_scale(1.0), 1.0);
+        assert_eq!(pulse_scale(0.0), 1.0);
+        assert!(pulse_scale(0.5) > 1.0);
+        assert!((pulse_scale(1.0) - 1.0).abs() < 1e-6);
+        assert_eq!(shake_offset(0.0), 0.0);
+        assert_eq!(shake_offset(1.0), 0.0);
+    }
+
+    #[test]
+    fn timers_stop_when_finished() {
+        let mut timer = start(Duration::from_millis(100));
+
+        assert_eq!(progress(&mut timer, Duration::from_millis(50)), Some(0.5));
+        assert_eq!(progress(&mut timer, Duration::from_millis(80)), Some(1.0));
+        assert_eq!(progress(&mut timer, Duration::from_millis(50)), None);
+        assert!(start(Duration::ZERO).is_none());
+    }
+}
@@ -1,68 +1,82 @@
-use bevy::{
-    input::keyboard::KeyboardInput,
-    prelude::*, audio::AudioSink,
-};
+use bevy::prelude::*;
+use unicode_normalization::UnicodeNormalization;
 
-use crate::SyllablesTree;
-use crate::defs::str_from_key;
-use crate::plugins::GameContext;
+use crate::{AudioClip, SyllableAudio, SyllablesTree};
+use crate::plugins::{AppState, GameContext, GameMode};
+use crate::plugins::input::TypingEvent;
+use crate::plugins::progress::ProgressEvent;
 
-use std::time::SystemTime;
+/// A recording to play. Game systems send it rather than using `Audio`, so that what is
+/// played can be checked without a sound device.
+pub struct PlaybackEvent(pub AudioClip);
 
 pub struct InternalAudioPlugin;
 
 impl Plugin for InternalAudioPlugin  {
     fn build(&self, app: &mut App) {
-        app.add_system(keyboard_input_system);
+        app.add_event::<PlaybackEvent>()
+           .add_system_set(SystemSet::on_update(AppState::InGame(GameMode::Free)).with_system(syllable_input_system));
     }
 }
 
-fn keyboard_input_system(syllab_tree: Res<SyllablesTree>, mut game_ctx: ResMut<GameContext>, audio: Res<Audio>, mut keyboard_input_events: EventReader<KeyboardInput>) {
-    use bevy::input::ButtonState;
+/// Plays the `PlaybackEvent`s on the sound device.
+pub struct AudioOutputPlugin;
 
-    let sys_time = SystemTime::now();
+impl Plugin for AudioOutputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::Last, playback_system);
+    }
+}
 
-    for event in keyboard_input_events.iter() {
-        match event.state {
-            ButtonState::Pressed => {
-                info!("Key press: {:?} ({})", event.key_code, event.scan_code);
+pub fn play_syllable(playback_events: &mut EventWriter<PlaybackEvent>, syllable_audio: &SyllableAudio) {
+    playback_events.send(PlaybackEvent(syllable_audio.pick().clone()));
+}
 
-                if let Ok(duration) = sys_time.duration_since(game_ctx.last_keypress) {
-                    const KEYPRESS_LIMIT_MS: u128 = 150;
-                    if duration.as_millis() < KEYPRESS_LIMIT_MS {
-                        println!("too soon, waiting for {}ms...", KEYPRESS_LIMIT_MS - duration.as_millis());
-                        continue;
-                    }
-                }
+fn playback_system(audio: Res<Audio>, mut playback_events: EventReader<PlaybackEvent>) {
+    for PlaybackEvent(clip) in playback_events.iter() {
+        audio.play_with_settings(clip.handle.clone(), PlaybackSettings::ONCE.with_volume(clip.volume));
+    }
+}
 
-                if let Some(key_code) = event.key_code {
-                    if let Some(c) = str_from_key(key_code).map(|str| str.to_lowercase()).and_then(|str| str.chars().next()) {
-                        game_ctx.syllable.push(c);
-                        game_ctx.is_syllable = false;
-                        info!("push key code syllable='{}'", game_ctx.syllable);                     
-                        if let Some((audio_handle, is_syllable)) = syllab_tree.get(game_ctx.syllable.clone()) {
-                            audio.play(audio_handle);
-                            game_ctx.is_syllable = is_syllable;
-                        }
-                        else {
-                            game_ctx.syllable.clear();
-                            game_ctx.syllable.push(c);
-                            if let Some((audio_handle, is_syllable)) = syllab_tree.get(game_ctx.syllable.clone()) {
-                                audio.play(audio_handle);
-                                game_ctx.is_syllable = is_syllable;
-                            }
-                        }
-                        info!("syllable={}", game_ctx.syllable);
+fn syllable_input_system(
+    syllab_tree: Res<SyllablesTree>,
+    mut game_ctx: ResMut<GameContext>,
+    mut playback_events: EventWriter<PlaybackEvent>,
+    mut typing_events: EventReader<TypingEvent>,
+    mut progress_events: EventWriter<ProgressEvent>) {
+    for event in typing_events.iter() {
+        match event {
+            TypingEvent::Char(c) => {
+                game_ctx.syllable.push(*c);
+                game_ctx.syllable = game_ctx.syllable.nfc().collect();
+                game_ctx.completed = 0;
+                info!("push key code syllable='{}'", game_ctx.syllable);
+                if let Some(segments) = syllab_tree.segment(&game_ctx.syllable) {
+                    if let Some(last_segment) = segments.last() {
+                        play_syllable(&mut playback_events, &last_segment.audio);
+                        progress_events.send(ProgressEvent::Typed(last_segment.syllable.clone()));
+                        // The last syllable is only done when no longer one starts with it
+                        game_ctx.completed = segments.len() - usize::from(!last_segment.is_leaf);
                     }
-                    else {
-                        game_ctx.syllable.clear();
+                    game_ctx.syllables = segments.into_iter().map(|segment| segment.syllable).collect();
+                }
+                else {
+                    let last_char = game_ctx.syllable.chars().last().unwrap_or(*c);
+                    game_ctx.syllable.clear();
+                    game_ctx.syllable.push(last_char);
+                    if let Some((syllable_audio, is_syllable)) = syllab_tree.get(game_ctx.syllable.clone()) {
+                        play_syllable(&mut playback_events, &syllable_audio);
+                        progress_events.send(ProgressEvent::Typed(game_ctx.syllable.clone()));
+                        game_ctx.completed = usize::from(is_syllable);
                     }
+                    game_ctx.syllables = vec![game_ctx.syllable.clone()];
                 }
-
-                game_ctx.last_keypress = SystemTime::now();
-            }
-            ButtonState::Released => {
-                info!("Key release: {:?} ({})", event.key_code, event.scan_code);
+                info!("syllable={}", game_ctx.syllable);
+            },
+            TypingEvent::Clear => {
+                game_ctx.syllable.clear();
+                game_ctx.syllables.clear();
+                game_ctx.completed = 0;
             }
         }
     }
@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use unicode_normalization::UnicodeNormalization;
+
+use std::time::Duration;
+
+use crate::{SyllableAudio, SyllablesTree};
+use crate::plugins::{AppState, GameContext, GameMode};
+use crate::plugins::audio::{play_syllable, PlaybackEvent};
+use crate::plugins::exercise::{pick_syllable, Level, FEEDBACK_DELAY};
+use crate::plugins::input::TypingEvent;
+use crate::plugins::progress::{Progress, ProgressEvent};
+use crate::plugins::voice::VoiceMenu;
+
+/// Shown until the player types the first letter.
+const PROMPT: &str = "?";
+
+/// Key playing the syllable again, it is never typed in this mode.
+pub const REPLAY_KEY: KeyCode = KeyCode::Space;
+
+/// Character `REPLAY_KEY` types with the text input method.
+pub const REPLAY_CHAR: char = ' ';
+
+#[derive(Default)]
+struct Dictation {
+    /// Syllable the player has to type, with its recordings.
+    target: Option<(String, SyllableAudio)>,
+    typed: String,
+    /// When the syllable was first played, in seconds since startup.
+    asked_at: f64,
+    /// Running while the answer is shown.
+    feedback_timer: Option<Timer>,
+    level: Level
+}
+
+pub struct DictationPlugin;
+
+impl Plugin for DictationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Dictation>()
+           .add_system_set(SystemSet::on_enter(AppState::InGame(GameMode::Dictation)).with_system(start_system))
+           .add_system_set(SystemSet::on_update(AppState::InGame(GameMode::Dictation))
+                .with_system(next_syllable_system)
+                .with_system(replay_system)
+                .with_system(answer_system));
+    }
+}
+
+fn start_system(mut dictation: ResMut<Dictation>) {
+    dictation.target = None;
+    dictation.feedback_timer = None;
+}
+
+fn show_typed(game_ctx: &mut GameContext, typed: &str) {
+    game_ctx.syllable = typed.to_string();
+    game_ctx.syllables = vec![if typed.is_empty() { PROMPT.to_string() } else { typed.to_string() }];
+}
+
+fn next_syllable_system(
+    syllab_tree: Res<SyllablesTree>,
+    progress: Res<Progress>,
+    time: Res<Time>,
+    mut playback_events: EventWriter<PlaybackEvent>,
+    mut dictation: ResMut<Dictation>,
+    mut game_ctx: ResMut<GameContext>,
+    mut progress_events: EventWriter<ProgressEvent>) {
+    if syllab_tree.is_changed() {
+        dictation.target = None;
+        dictation.feedback_timer = None;
+    }
+
+    if let Some(timer) = &mut dictation.feedback_timer {
+        if !timer.tick(time.delta()).finished() {
+            return;
+        }
+        dictation.feedback_timer = None;
+    }
+    else if dictation.target.is_some() {
+        return;
+    }
+
+    let previous = dictation.target.take().map(|(syllable, _)| syllable);
+    dictation.target = pick_syllable(&syllab_tree, &progress, dictation.level.level, previous.as_deref());
+    dictation.typed.clear();
+    game_ctx.feedback = None;
+    game_ctx.completed = 0;
+    show_typed(&mut game_ctx, "");
+
+    if let Some((syllable, syllable_audio)) = dictation.target.clone() {
+        info!("Dictation: '{}'", syllable);
+        dictation.asked_at = time.seconds_since_startup();
+        play_syllable(&mut playback_events, &syllable_audio);
+        progress_events.send(ProgressEvent::Heard(syllable));
+    }
+}
+
+fn replay_system(
+    keyboard: Res<Input<KeyCode>>,
+    voice_menu: Res<VoiceMenu>,
+    mut playback_events: EventWriter<PlaybackEvent>,
+    dictation: Res<Dictation>,
+    mut progress_events: EventWriter<ProgressEvent>) {
+    if voice_menu.open || dictation.feedback_timer.is_some() || !keyboard.just_pressed(REPLAY_KEY) {
+        return;
+    }
+
+    if let Some((syllable, syllable_audio)) = &dictation.target {
+        play_syllable(&mut playback_events, syllable_audio);
+        progress_events.send(ProgressEvent::Heard(syllable.clone()));
+    }
+}
+
+/// Compare what is typed with the syllable, as soon as it matches or cannot match anymore.
+fn answer_system(
+    syllab_tree: Res<SyllablesTree>,
+    time: Res<Time>,
+    mut playback_events: EventWriter<PlaybackEvent>,
+    mut dictation: ResMut<Dictation>,
+    mut game_ctx: ResMut<GameContext>,
+    mut typing_events: EventReader<TypingEvent>,
+    mut progress_events: EventWriter<ProgressEvent>) {
+    for event in typing_events.iter() {
+        if dictation.feedback_timer.is_some() {
+            continue;
+        }
+
+        let (syllable, syllable_audio) = match &dictation.target {
+            Some(target) => target.clone(),
+            None => continue
+        };
+
+        match event {
+            TypingEvent::Char(c) => {
+                dictation.typed.push(*c);
+                dictation.typed = dictation.typed.nfc().collect();
+            },
+            TypingEvent::Clear => dictation.typed.clear()
+        }
+
+        let typed = syllab_tree.normalize(&dictation.typed);
+        let expected = syllab_tree.normalize(&syllable);
+        let correct = if typed == expected {
+            true
+        }
+        else if typed.is_empty() || expected.starts_with(&typed) {
+            show_typed(&mut game_ctx, &dictation.typed);
+            continue;
+        }
+        else {
+            false
+        };
+
+        info!("Dictation: '{}' for '{}', {}", dictation.typed, syllable, if correct { "right" } else { "wrong" });
+
+        // Let the player hear again what was expected
+        if !correct {
+            play_syllable(&mut playback_events, &syllable_audio);
+        }
+
+        show_typed(&mut game_ctx, &dictation.typed);
+        game_ctx.feedback = Some(correct);
+        dictation.level.record(correct);
+        dictation.feedback_timer = Some(Timer::from_seconds(FEEDBACK_DELAY, false));
+        let response_time = Duration::from_secs_f64(time.seconds_since_startup() - dictation.asked_at);
+        progress_events.send(ProgressEvent::Answered { syllable, correct, response_time });
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::{SyllableAudio, SyllablesTree};
+use crate::difficulty::{self, MAX_LEVEL, START_LEVEL};
+use crate::plugins::progress::Progress;
+use crate::scheduler;
+
+use std::time::SystemTime;
+
+/// Seconds the answer stays on screen before the next syllable.
+pub const FEEDBACK_DELAY: f32 = 1.5;
+
+/// Right answers in a row that raise the level.
+const LEVEL_UP_STREAK: u32 = 3;
+
+/// Wrong answers in a row that lower the level.
+const LEVEL_DOWN_STREAK: u32 = 2;
+
+/// Difficulty of the syllables asked for, following how well the player answers.
+pub struct Level {
+    pub level: u32,
+    right_streak: u32,
+    wrong_streak: u32
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level {
+            level: START_LEVEL,
+            right_streak: 0,
+            wrong_streak: 0
+        }
+    }
+}
+
+impl Level {
+    /// Track the streaks and move the level up or down accordingly.
+    pub fn record(&mut self, correct: bool) {
+        if correct {
+            self.right_streak += 1;
+            self.wrong_streak = 0;
+            if self.right_streak >= LEVEL_UP_STREAK && self.level < MAX_LEVEL {
+                self.level += 1;
+                self.right_streak = 0;
+                info!("Level up: {}", self.level);
+            }
+        }
+        else {
+            self.wrong_streak += 1;
+            self.right_streak = 0;
+            if self.wrong_streak >= LEVEL_DOWN_STREAK && self.level > 1 {
+                self.level -= 1;
+                self.wrong_streak = 0;
+                info!("Level down: {}", self.level);
+            }
+        }
+    }
+}
+
+/// Pick a syllable to ask for among the ones the Leitner boxes say are due, favouring the
+/// lower boxes and the syllables close to `level`. When none is due, any syllable can come.
+/// Digits are left out, and `previous` is not asked twice in a row.
+pub fn pick_syllable(syllab_tree: &SyllablesTree, progress: &Progress, level: u32, previous: Option<&str>) -> Option<(String, SyllableAudio)> {
+    let now = SystemTime::now();
+    let mut syllables: Vec<(String, SyllableAudio)> = syllab_tree.syllables()
+        .into_iter()
+        .filter(|(syllable, _)| syllable.chars().all(char::is_alphabetic))
+        .collect();
+    if syllables.len() > 1 {
+        syllables.retain(|(syllable, _)| Some(syllable.as_str()) != previous);
+    }
+
+    let level_weight = |syllable: &str| difficulty::level_weight(difficulty::difficulty(syllable), level);
+
+    let pick = difficulty::pick_weighted(&syllables, |(syllable, _)| {
+        scheduler::priority(progress.card(syllable), now).unwrap_or(0.0) * level_weight(syllable)
+    })
+    .or_else(|| difficulty::pick_weighted(&syllables, |(syllable, _)| level_weight(syllable)));
+
+    pick.cloned()
+}
@@ -0,0 +1,305 @@
+use bevy::{
+    ecs::{schedule::ShouldRun, system::SystemParam},
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::layout::KeyboardLayout;
+use crate::plugins::{in_game, AppState, GameMode, InGameUi, InputMethod, InputSettings, Theme};
+use crate::plugins::dictation::{REPLAY_CHAR, REPLAY_KEY};
+use crate::plugins::voice::VoiceMenu;
+
+use std::collections::{HashSet, VecDeque};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Shown while the keys are ignored because of key-mashing.
+const MASHING_HINT: &str = "Gently, one key at a time";
+
+/// What the player typed, whichever `InputMethod` is used.
+pub enum TypingEvent {
+    /// A lowercase letter or digit, or a combining mark applying to the previous character.
+    Char(char),
+    /// Any other key, which starts a new word.
+    Clear
+}
+
+/// Spacing accent left by a dead key that the system did not compose.
+#[derive(Default)]
+struct PendingAccent(Option<char>);
+
+/// A key as told apart for debouncing.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Key {
+    Code(Option<KeyCode>, u32),
+    Char(char)
+}
+
+/// Recent key presses, to tell typing from a key repeating or key-mashing.
+#[derive(Default)]
+pub struct KeyHistory {
+    /// Keys down, which repeat their press until released.
+    held: HashSet<Key>,
+    /// Presses of held keys this frame, each followed by its character again with
+    /// `InputMethod::Text`.
+    repeats: usize,
+    /// When the keys were pressed lately, oldest first, repeats left out.
+    presses: VecDeque<Duration>
+}
+
+impl KeyHistory {
+    /// Record that `key` went down, and tell whether it was up before.
+    fn hold(&mut self, key: Key) -> bool {
+        let is_new = self.held.insert(key);
+        if !is_new {
+            debug!("Ignoring {:?}, held down", key);
+        }
+        is_new
+    }
+
+    fn release(&mut self, key: Key) {
+        self.held.remove(&key);
+    }
+
+    /// Take one of the characters sent again by a held key, if any is due.
+    fn take_repeat(&mut self) -> bool {
+        let is_repeat = self.repeats > 0;
+        self.repeats = self.repeats.saturating_sub(1);
+        is_repeat
+    }
+
+    /// Record a key press at `now`, since startup, and tell whether to type it.
+    fn accept(&mut self, now: Duration, input_settings: &InputSettings) -> bool {
+        while self.presses.front().is_some_and(|at| now - *at >= input_settings.mash_window) {
+            self.presses.pop_front();
+        }
+        self.presses.push_back(now);
+
+        !self.is_mashing(now, input_settings)
+    }
+
+    /// Whether more than `InputSettings::mash_limit` keys were pressed within the mashing window.
+    pub fn is_mashing(&self, now: Duration, input_settings: &InputSettings) -> bool {
+        input_settings.mash_limit > 0
+            && self.presses.iter().filter(|at| now - **at < input_settings.mash_window).count() > input_settings.mash_limit as usize
+    }
+}
+
+/// Keys the game uses for itself rather than typing with them.
+#[derive(SystemParam)]
+struct ReservedKeys<'w, 's> {
+    app_state: Res<'w, State<AppState>>,
+    voice_menu: Res<'w, VoiceMenu>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>
+}
+
+impl<'w, 's> ReservedKeys<'w, 's> {
+    /// Every key is outside of the game, where the menus read the keys themselves, and while
+    /// the voice menu is open.
+    fn all(&self) -> bool {
+        self.app_state.current().game_mode().is_none() || self.voice_menu.open
+    }
+
+    fn contains(&self, key: Key) -> bool {
+        let is_replay = matches!(key, Key::Code(Some(REPLAY_KEY), _) | Key::Char(REPLAY_CHAR));
+        is_replay && *self.app_state.current() == AppState::InGame(GameMode::Dictation)
+    }
+}
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TypingEvent>()
+           .init_resource::<KeyboardLayout>()
+           .init_resource::<PendingAccent>()
+           .init_resource::<KeyHistory>()
+           .add_startup_system(setup)
+           .add_system(keyboard_input_system.with_run_criteria(input_method_is_keys))
+           .add_system(key_repeat_system.with_run_criteria(input_method_is_text).before(text_input_system))
+           .add_system(text_input_system.with_run_criteria(input_method_is_text))
+           .add_system(mashing_hint_system.with_run_criteria(in_game));
+    }
+}
+
+#[derive(Component)]
+struct MashingHintText;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>, input_settings: Res<InputSettings>) {
+    match KeyboardLayout::load(&input_settings.layout, asset_server.asset_io()) {
+        Ok(layout) => {
+            info!("Keyboard layout: {}", layout.name);
+            commands.insert_resource(layout);
+        },
+        Err(err) => error!("Failed to load keyboard layout '{}': {}", input_settings.layout, err)
+    }
+
+    let text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 40.0,
+            color: theme.text
+        }
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        position: UiRect {
+            left: Val::Px(200.0),
+            bottom: Val::Px(60.0),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn()
+        .insert_bundle(text)
+        .insert(MashingHintText)
+        .insert(InGameUi(None));
+}
+
+/// Keys are followed in the menus too, to know which are held when the game starts.
+fn input_method_is_keys(input_settings: Res<InputSettings>) -> ShouldRun {
+    if input_settings.method == InputMethod::Keys { ShouldRun::Yes } else { ShouldRun::No }
+}
+
+fn input_method_is_text(input_settings: Res<InputSettings>) -> ShouldRun {
+    if input_settings.method == InputMethod::Text { ShouldRun::Yes } else { ShouldRun::No }
+}
+
+fn keyboard_input_system(
+    time: Res<Time>,
+    input_settings: Res<InputSettings>,
+    mut key_history: ResMut<KeyHistory>,
+    layout: Res<KeyboardLayout>,
+    reserved_keys: ReservedKeys,
+    mut keyboard_input_events: EventReader<KeyboardInput>,
+    mut typing_events: EventWriter<TypingEvent>) {
+    for event in keyboard_input_events.iter() {
+        let key = Key::Code(event.key_code, event.scan_code);
+        match event.state {
+            ButtonState::Pressed => {
+                info!("Key press: {:?} ({})", event.key_code, event.scan_code);
+
+                if !key_history.hold(key) || reserved_keys.all() || reserved_keys.contains(key)
+                    || !key_history.accept(time.time_since_startup(), &input_settings) {
+                    continue;
+                }
+
+                if let Some(c) = layout.char_from_key(event.key_code, event.scan_code) {
+                    typing_events.send(TypingEvent::Char(c));
+                }
+                else if event.key_code.is_some() {
+                    typing_events.send(TypingEvent::Clear);
+                }
+            }
+            ButtonState::Released => {
+                info!("Key release: {:?} ({})", event.key_code, event.scan_code);
+                key_history.release(key);
+            }
+        }
+    }
+}
+
+/// Whether `key_code` only changes what the other keys type. Some systems repeat it when held,
+/// without a character.
+fn is_modifier(key_code: Option<KeyCode>) -> bool {
+    matches!(key_code, Some(
+        KeyCode::LShift | KeyCode::RShift | KeyCode::LControl | KeyCode::RControl
+            | KeyCode::LAlt | KeyCode::RAlt | KeyCode::LWin | KeyCode::RWin
+    ))
+}
+
+/// Count the presses of held keys, which the system follows with their character again.
+fn key_repeat_system(mut key_history: ResMut<KeyHistory>, mut keyboard_input_events: EventReader<KeyboardInput>) {
+    key_history.repeats = 0;
+    for event in keyboard_input_events.iter() {
+        let key = Key::Code(event.key_code, event.scan_code);
+        match event.state {
+            ButtonState::Pressed => {
+                if !key_history.hold(key) && !is_modifier(event.key_code) {
+                    key_history.repeats += 1;
+                }
+            },
+            ButtonState::Released => key_history.release(key)
+        }
+    }
+}
+
+/// Combining form of the spacing accents some systems send for an uncomposed dead key.
+fn combining_accent(c: char) -> Option<char> {
+    match c {
+        '`' => Some('\u{300}'),
+        '´' => Some('\u{301}'),
+        '^' => Some('\u{302}'),
+        '~' => Some('\u{303}'),
+        '¨' => Some('\u{308}'),
+        _ => None
+    }
+}
+
+fn text_input_system(
+    time: Res<Time>,
+    input_settings: Res<InputSettings>,
+    mut key_history: ResMut<KeyHistory>,
+    reserved_keys: ReservedKeys,
+    mut pending_accent: ResMut<PendingAccent>,
+    mut received_character_events: EventReader<ReceivedCharacter>,
+    mut typing_events: EventWriter<TypingEvent>) {
+    if reserved_keys.all() {
+        received_character_events.clear();
+        return;
+    }
+
+    for event in received_character_events.iter() {
+        info!("Received character: {:?}", event.char);
+
+        if key_history.take_repeat() {
+            debug!("Ignoring {:?}, its key is held down", event.char);
+            continue;
+        }
+
+        if reserved_keys.contains(Key::Char(event.char)) || !key_history.accept(time.time_since_startup(), &input_settings) {
+            continue;
+        }
+
+        if let Some(accent) = combining_accent(event.char) {
+            pending_accent.0 = Some(accent);
+            continue;
+        }
+
+        if event.char.is_alphanumeric() || is_combining_mark(event.char) {
+            let text: String = match pending_accent.0.take() {
+                Some(accent) => {
+                    // Keep the accent only if it composes with the letter into a single character
+                    let composed: String = [event.char, accent].into_iter().nfc().collect();
+                    if composed.chars().count() == 1 { composed } else { event.char.to_string() }
+                },
+                None => event.char.to_string()
+            };
+
+            for c in text.to_lowercase().nfc() {
+                typing_events.send(TypingEvent::Char(c));
+            }
+        }
+        else {
+            pending_accent.0 = None;
+            typing_events.send(TypingEvent::Clear);
+        }
+    }
+}
+
+fn mashing_hint_system(
+    time: Res<Time>,
+    input_settings: Res<InputSettings>,
+    key_history: Res<KeyHistory>,
+    mut query: Query<&mut Text, With<MashingHintText>>) {
+    let hint = if key_history.is_mashing(time.time_since_startup(), &input_settings) { MASHING_HINT } else { "" };
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != hint {
+            text.sections[0].value = hint.to_string();
+        }
+    }
+}
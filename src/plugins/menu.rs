@@ -0,0 +1,406 @@
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+
+use crate::plugins::{in_game, AppState, GameMode, Progress, Theme};
+use crate::plugins::voice::VoiceMenu;
+use crate::profile::{Outcome, Profile};
+
+/// Screen shown by the menu text, outside of the game.
+#[derive(Default)]
+struct Menu {
+    title: String,
+    /// Text under the title.
+    lines: Vec<String>,
+    /// Entries chosen with Up, Down and Return.
+    items: Vec<String>,
+    cursor: usize,
+    /// Keys to press, at the bottom.
+    hint: String
+}
+
+impl Menu {
+    fn show(&mut self, title: &str, lines: Vec<String>, items: Vec<String>, hint: &str) {
+        self.title = title.to_string();
+        self.lines = lines;
+        self.cursor = self.cursor.min(items.len().saturating_sub(1));
+        self.items = items;
+        self.hint = hint.to_string();
+    }
+
+    /// Move the cursor with Up and Down, and return the entry chosen with Return.
+    fn choose(&mut self, keyboard: &mut Input<KeyCode>) -> Option<usize> {
+        let count = self.items.len();
+        if count == 0 {
+            return None;
+        }
+
+        if keyboard.just_pressed(KeyCode::Up) {
+            self.cursor = (self.cursor + count - 1) % count;
+        }
+        else if keyboard.just_pressed(KeyCode::Down) {
+            self.cursor = (self.cursor + 1) % count;
+        }
+        else if keyboard.clear_just_pressed(KeyCode::Return) {
+            return Some(self.cursor);
+        }
+        None
+    }
+}
+
+/// Keyboard of the menus, left to the voice menu while it is open.
+#[derive(SystemParam)]
+struct MenuKeys<'w, 's> {
+    keyboard: ResMut<'w, Input<KeyCode>>,
+    voice_menu: Res<'w, VoiceMenu>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>
+}
+
+impl<'w, 's> MenuKeys<'w, 's> {
+    /// `None` while the voice menu is open, and on the frame it closes so that the key closing
+    /// it does not reach the menu too.
+    fn get(&mut self) -> Option<&mut Input<KeyCode>> {
+        if self.voice_menu.open || self.voice_menu.is_changed() {
+            None
+        }
+        else {
+            Some(&mut self.keyboard)
+        }
+    }
+}
+
+/// Name being typed for a new profile, in the profile selection.
+#[derive(Default)]
+struct ProfileEntry {
+    profiles: Vec<String>,
+    new_name: Option<String>,
+    error: Option<String>
+}
+
+const PAUSE_ITEMS: [&str; 3] = ["Resume", "Change mode", "End session"];
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Menu>()
+           .init_resource::<ProfileEntry>()
+           .add_startup_system(setup)
+           .add_system(menu_text_system)
+           .add_system(pause_system.with_run_criteria(in_game))
+           .add_system_set(SystemSet::on_enter(AppState::Title).with_system(title_enter_system))
+           .add_system_set(SystemSet::on_update(AppState::Title).with_system(title_system))
+           .add_system_set(SystemSet::on_enter(AppState::ProfileSelect).with_system(profile_select_enter_system))
+           .add_system_set(SystemSet::on_update(AppState::ProfileSelect).with_system(profile_select_system))
+           .add_system_set(SystemSet::on_enter(AppState::ModeSelect).with_system(mode_select_enter_system))
+           .add_system_set(SystemSet::on_update(AppState::ModeSelect).with_system(mode_select_system))
+           .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(paused_enter_system))
+           .add_system_set(SystemSet::on_update(AppState::Paused).with_system(paused_system))
+           .add_system_set(SystemSet::on_enter(AppState::Results).with_system(results_enter_system))
+           .add_system_set(SystemSet::on_update(AppState::Results).with_system(results_system));
+    }
+}
+
+#[derive(Component)]
+struct MenuText;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 50.0,
+            color: theme.text
+        }
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        position: UiRect {
+            left: Val::Px(200.0),
+            top: Val::Px(120.0),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn()
+        .insert_bundle(text)
+        .insert(MenuText);
+}
+
+fn menu_text_system(app_state: Res<State<AppState>>, menu: Res<Menu>, mut query: Query<&mut Text, With<MenuText>>) {
+    if !menu.is_changed() && !app_state.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = if app_state.current().game_mode().is_some() {
+            String::new()
+        }
+        else {
+            let mut lines = vec![menu.title.clone(), String::new()];
+            lines.extend(menu.lines.iter().cloned());
+            lines.extend(menu.items.iter()
+                .enumerate()
+                .map(|(i, item)| format!("{} {}", if i == menu.cursor { ">" } else { " " }, item)));
+            lines.push(String::new());
+            lines.push(menu.hint.clone());
+            lines.join("\n")
+        };
+    }
+}
+
+fn set_state(app_state: &mut State<AppState>, state: AppState) {
+    if let Err(err) = app_state.set(state) {
+        warn!("Cannot go to {:?}: {:?}", state, err);
+    }
+}
+
+fn title_enter_system(mut menu: ResMut<Menu>) {
+    menu.show("Syllabs", vec!["Type letters, hear syllables.".to_string()], Vec::new(), "Return: start    Escape: quit");
+}
+
+fn title_system(mut menu_keys: MenuKeys, mut app_state: ResMut<State<AppState>>, mut exit_events: EventWriter<AppExit>) {
+    let keyboard = match menu_keys.get() {
+        Some(keyboard) => keyboard,
+        None => return
+    };
+
+    if keyboard.clear_just_pressed(KeyCode::Return) {
+        set_state(&mut app_state, AppState::ProfileSelect);
+    }
+    else if keyboard.clear_just_pressed(KeyCode::Escape) {
+        exit_events.send(AppExit);
+    }
+}
+
+fn show_profiles(menu: &mut Menu, profile_entry: &ProfileEntry) {
+    let lines = match (&profile_entry.new_name, &profile_entry.error) {
+        (Some(new_name), _) => vec![format!("New profile: {}_", new_name)],
+        (None, Some(error)) => vec![error.clone()],
+        (None, None) => Vec::new()
+    };
+
+    let mut items = profile_entry.profiles.clone();
+    items.push("New profile".to_string());
+
+    let hint = if profile_entry.new_name.is_some() { "Type a name, Return: create    Escape: cancel" } else { "Return: choose    Escape: back" };
+    menu.show("Who is playing?", lines, items, hint);
+}
+
+fn profile_select_enter_system(mut menu: ResMut<Menu>, mut profile_entry: ResMut<ProfileEntry>, progress: Option<Res<Progress>>) {
+    *profile_entry = ProfileEntry::default();
+    match Profile::list() {
+        Ok(profiles) => profile_entry.profiles = profiles,
+        Err(err) => profile_entry.error = Some(err.to_string())
+    }
+
+    menu.cursor = progress
+        .and_then(|progress| profile_entry.profiles.iter().position(|name| name == progress.name()))
+        .unwrap_or(0);
+    show_profiles(&mut menu, &profile_entry);
+}
+
+fn profile_select_system(
+    mut commands: Commands,
+    mut menu_keys: MenuKeys,
+    mut received_character_events: EventReader<ReceivedCharacter>,
+    mut app_state: ResMut<State<AppState>>,
+    mut menu: ResMut<Menu>,
+    mut profile_entry: ResMut<ProfileEntry>,
+    progress: Option<Res<Progress>>) {
+    let keyboard = match menu_keys.get() {
+        Some(keyboard) => keyboard,
+        None => {
+            received_character_events.clear();
+            return;
+        }
+    };
+
+    let chosen = match profile_entry.new_name.clone() {
+        Some(mut new_name) => {
+            let typed: String = received_character_events.iter()
+                .map(|event| event.char)
+                .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                .collect();
+            new_name.push_str(&typed);
+
+            let erase = keyboard.clear_just_pressed(KeyCode::Back);
+            if erase {
+                new_name.pop();
+            }
+
+            if keyboard.clear_just_pressed(KeyCode::Escape) {
+                profile_entry.new_name = None;
+                None
+            }
+            else if keyboard.clear_just_pressed(KeyCode::Return) && Profile::is_valid_name(&new_name) {
+                Some(new_name)
+            }
+            else {
+                if erase || !typed.is_empty() {
+                    profile_entry.new_name = Some(new_name);
+                }
+                None
+            }
+        },
+        None => {
+            received_character_events.clear();
+
+            if keyboard.clear_just_pressed(KeyCode::Escape) {
+                set_state(&mut app_state, AppState::Title);
+                return;
+            }
+
+            match menu.choose(keyboard) {
+                Some(i) if i < profile_entry.profiles.len() => Some(profile_entry.profiles[i].clone()),
+                Some(_) => {
+                    profile_entry.new_name = Some(String::new());
+                    None
+                },
+                None => None
+            }
+        }
+    };
+
+    if let Some(name) = chosen {
+        if progress.as_ref().map(|progress| progress.name()) != Some(name.as_str()) {
+            match Profile::load(&name) {
+                Ok(profile) => {
+                    if let Some(progress) = &progress {
+                        progress.save();
+                    }
+                    info!("Profile '{}': {} attempt(s) so far", profile.name, profile.attempts.len());
+                    commands.insert_resource(Progress::new(profile));
+                },
+                Err(err) => {
+                    profile_entry.new_name = None;
+                    profile_entry.error = Some(err.to_string());
+                    show_profiles(&mut menu, &profile_entry);
+                    return;
+                }
+            }
+        }
+
+        set_state(&mut app_state, AppState::ModeSelect);
+        return;
+    }
+
+    if profile_entry.is_changed() {
+        show_profiles(&mut menu, &profile_entry);
+    }
+}
+
+fn mode_select_enter_system(mut menu: ResMut<Menu>, progress: Option<Res<Progress>>) {
+    let items = GameMode::value_variants().iter()
+        .filter_map(|mode| mode.to_possible_value())
+        .map(|value| match value.get_help() {
+            Some(help) => format!("{}: {}", value.get_name(), help),
+            None => value.get_name().to_string()
+        })
+        .collect();
+
+    let title = match progress {
+        Some(progress) => format!("What do we play, {}?", progress.name()),
+        None => "What do we play?".to_string()
+    };
+    menu.cursor = 0;
+    menu.show(&title, Vec::new(), items, "Return: play    Escape: back");
+}
+
+fn mode_select_system(mut menu_keys: MenuKeys, mut app_state: ResMut<State<AppState>>, mut menu: ResMut<Menu>) {
+    let keyboard = match menu_keys.get() {
+        Some(keyboard) => keyboard,
+        None => return
+    };
+
+    if keyboard.clear_just_pressed(KeyCode::Escape) {
+        set_state(&mut app_state, AppState::ProfileSelect);
+    }
+    else if let Some(i) = menu.choose(keyboard) {
+        set_state(&mut app_state, AppState::InGame(GameMode::value_variants()[i]));
+    }
+}
+
+/// Escape pauses the game, unless it closes the voice menu.
+fn pause_system(mut keyboard: ResMut<Input<KeyCode>>, voice_menu: Res<VoiceMenu>, mut app_state: ResMut<State<AppState>>) {
+    if voice_menu.open || voice_menu.is_changed() || !keyboard.clear_just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    if let Err(err) = app_state.push(AppState::Paused) {
+        warn!("Cannot pause: {:?}", err);
+    }
+}
+
+fn paused_enter_system(mut menu: ResMut<Menu>) {
+    menu.cursor = 0;
+    menu.show("Paused", Vec::new(), PAUSE_ITEMS.iter().map(|item| item.to_string()).collect(), "Return: choose    Escape: resume");
+}
+
+fn paused_system(mut menu_keys: MenuKeys, mut app_state: ResMut<State<AppState>>, mut menu: ResMut<Menu>) {
+    let keyboard = match menu_keys.get() {
+        Some(keyboard) => keyboard,
+        None => return
+    };
+
+    let result = if keyboard.clear_just_pressed(KeyCode::Escape) {
+        app_state.pop()
+    }
+    else {
+        match menu.choose(keyboard) {
+            Some(0) => app_state.pop(),
+            Some(1) => app_state.replace(AppState::ModeSelect),
+            Some(_) => app_state.replace(AppState::Results),
+            None => Ok(())
+        }
+    };
+
+    if let Err(err) = result {
+        warn!("Cannot leave the pause: {:?}", err);
+    }
+}
+
+fn results_enter_system(mut menu: ResMut<Menu>, progress: Option<Res<Progress>>) {
+    let session = progress.as_ref().map(|progress| progress.session()).unwrap_or_default();
+    let count = |outcome: Outcome| session.iter().filter(|attempt| attempt.outcome == outcome).count();
+
+    let mut lines = vec![
+        format!("Right answers: {}", count(Outcome::Right)),
+        format!("Wrong answers: {}", count(Outcome::Wrong)),
+        format!("Syllables heard: {}, typed: {}", count(Outcome::Heard), count(Outcome::Typed))
+    ];
+
+    let missed: BTreeSet<&str> = session.iter()
+        .filter(|attempt| attempt.outcome == Outcome::Wrong)
+        .map(|attempt| attempt.syllable.as_str())
+        .collect();
+    if !missed.is_empty() {
+        lines.push(format!("To practise: {}", missed.into_iter().collect::<Vec<_>>().join(", ")));
+    }
+
+    if let Some(progress) = &progress {
+        progress.save();
+    }
+
+    menu.show("Well done!", lines, Vec::new(), "Return: play again    Escape: title");
+}
+
+fn results_system(mut menu_keys: MenuKeys, mut app_state: ResMut<State<AppState>>) {
+    let keyboard = match menu_keys.get() {
+        Some(keyboard) => keyboard,
+        None => return
+    };
+
+    if keyboard.clear_just_pressed(KeyCode::Return) {
+        set_state(&mut app_state, AppState::ModeSelect);
+    }
+    else if keyboard.clear_just_pressed(KeyCode::Escape) {
+        set_state(&mut app_state, AppState::Title);
+    }
+}
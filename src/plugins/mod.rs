@@ -1,26 +1,172 @@
-use bevy::prelude::*;
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use clap::ValueEnum;
 
+use std::time::Duration;
+
+mod animation;
 mod audio;
+mod dictation;
+mod exercise;
+mod input;
+mod menu;
+mod progress;
+mod reading;
+#[cfg(test)]
+mod tests;
 mod text;
+mod theme;
+mod voice;
+mod words;
 
-use audio::InternalAudioPlugin;
+use animation::AnimationPlugin;
+use audio::{AudioOutputPlugin, InternalAudioPlugin};
+use dictation::DictationPlugin;
+use input::InputPlugin;
+use menu::MenuPlugin;
+use progress::ProgressPlugin;
+use reading::ReadingPlugin;
 use text::TextPlugin;
-use std::time::SystemTime;
+use voice::VoicePlugin;
+use words::WordsPlugin;
+
+pub use animation::AnimationSettings;
+pub use progress::Progress;
+pub use text::DisplayMode;
+pub use theme::{AccessibilityPreset, Theme};
+pub use voice::VoiceRequest;
+pub use words::WordListName;
+
+/// What the player is asked to do.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    /// Type freely and hear every syllable
+    Free,
+    /// Hear a syllable and type it, Space plays it again
+    Dictation,
+    /// Read a syllable aloud, a parent presses Return when right or Backspace to try again
+    Reading,
+    /// Type a word syllable by syllable, then hear it whole
+    Words
+}
+
+/// Screens of the game. Systems are scoped to them with `SystemSet::on_update`, and to a
+/// single game mode with `AppState::InGame`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AppState {
+    Title,
+    ProfileSelect,
+    ModeSelect,
+    InGame(GameMode),
+    /// Pushed over `InGame` with Escape.
+    Paused,
+    /// End of the session.
+    Results
+}
+
+impl AppState {
+    pub fn game_mode(&self) -> Option<GameMode> {
+        match self {
+            AppState::InGame(mode) => Some(*mode),
+            _ => None
+        }
+    }
+}
+
+/// Run criteria for the systems shared by every game mode.
+fn in_game(app_state: Res<State<AppState>>) -> ShouldRun {
+    if app_state.current().game_mode().is_some() { ShouldRun::Yes } else { ShouldRun::No }
+}
+
+/// UI shown only while playing, in every mode when `None`.
+#[derive(Component)]
+pub struct InGameUi(pub Option<GameMode>);
+
+/// Where typed characters come from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum InputMethod {
+    /// Key presses, turned into letters by the keyboard layout
+    Keys,
+    /// Characters composed by the system, including dead keys
+    Text
+}
+
+pub struct InputSettings {
+    pub method: InputMethod,
+    /// Keys are ignored while more than this many are pressed within `mash_window`, never when 0.
+    pub mash_limit: u32,
+    pub mash_window: Duration,
+    /// Name of the keyboard layout accented letters are typed with.
+    pub layout: String
+}
 
 pub struct GameContext {
     syllable: String,
-    is_syllable: bool,
-    last_keypress: SystemTime
+    /// Split of `syllable` into known syllables, as played back to the player.
+    syllables: Vec<String>,
+    /// How many of the first `syllables` are fully typed, they are shown highlighted.
+    completed: usize,
+    /// Whether the last answer was right, in the modes asking for a syllable.
+    feedback: Option<bool>
+}
+
+impl GameContext {
+    fn clear(&mut self) {
+        self.syllable.clear();
+        self.syllables.clear();
+        self.completed = 0;
+        self.feedback = None;
+    }
 }
 
-pub struct GamePlugin;
+pub struct GamePlugin {
+    /// Mode to start playing right away, skipping the menus.
+    pub start_mode: Option<GameMode>
+}
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ClearColor(Color::BLACK))
+        let start_state = match self.start_mode {
+            Some(mode) => AppState::InGame(mode),
+            None => AppState::Title
+        };
+
+        let background = app.world.get_resource_or_insert_with(Theme::default).background;
+
+        app.insert_resource(ClearColor(background))
            .insert_resource(GameContext {
-                syllable: String::new(), is_syllable: false, last_keypress: SystemTime::now() })
+                syllable: String::new(), syllables: Vec::new(), completed: 0, feedback: None })
+           .add_state(start_state)
+           .add_system(in_game_ui_system);
+
+        for mode in GameMode::value_variants() {
+            app.add_system_set(SystemSet::on_enter(AppState::InGame(*mode)).with_system(start_game_system));
+        }
+
+        app.add_plugin(MenuPlugin)
+           .add_plugin(VoicePlugin)
+           .add_plugin(InputPlugin)
+           .add_plugin(ProgressPlugin)
            .add_plugin(InternalAudioPlugin)
-           .add_plugin(TextPlugin);
+           .add_plugin(AudioOutputPlugin)
+           .add_plugin(DictationPlugin)
+           .add_plugin(ReadingPlugin)
+           .add_plugin(WordsPlugin)
+           .add_plugin(TextPlugin)
+           .add_plugin(AnimationPlugin);
+    }
+}
+
+fn start_game_system(mut game_ctx: ResMut<GameContext>, progress: Option<ResMut<Progress>>) {
+    game_ctx.clear();
+    if let Some(mut progress) = progress {
+        progress.start_session();
+    }
+}
+
+fn in_game_ui_system(app_state: Res<State<AppState>>, mut query: Query<(&InGameUi, &mut Visibility)>) {
+    let mode = app_state.current().game_mode();
+    for (in_game_ui, mut visibility) in query.iter_mut() {
+        visibility.is_visible = mode.is_some() && (in_game_ui.0.is_none() || in_game_ui.0 == mode);
     }
 }
@@ -0,0 +1,146 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use std::time::{Duration, SystemTime};
+
+use crate::plugins::{AppState, GameMode};
+use crate::profile::{Attempt, Outcome, Profile};
+use crate::scheduler::Card;
+
+/// Seconds after an answer the profile is saved, so that a crash loses little without writing
+/// the file on every answer.
+const SAVE_DELAY: f32 = 30.0;
+
+/// Something the player did with a syllable, whatever the game mode.
+pub enum ProgressEvent {
+    /// The syllable was played for the player to recognize.
+    Heard(String),
+    /// The player typed the syllable and heard it back.
+    Typed(String),
+    /// The player was asked for the syllable, and answered after `response_time`.
+    Answered { syllable: String, correct: bool, response_time: Duration }
+}
+
+/// Profile of the player, updated with everything they do.
+pub struct Progress {
+    profile: Profile,
+    /// Attempts of this session, kept when the profile drops its oldest ones.
+    session: Vec<Attempt>
+}
+
+impl Progress {
+    pub fn new(profile: Profile) -> Progress {
+        Progress { profile, session: Vec::new() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.profile.name
+    }
+
+    /// Start counting the attempts of a new session.
+    pub fn start_session(&mut self) {
+        self.session.clear();
+    }
+
+    /// Attempts since the session started.
+    pub fn session(&self) -> &[Attempt] {
+        &self.session
+    }
+
+    fn record(&mut self, syllable: &str, game_mode: GameMode, outcome: Outcome, at: SystemTime, response_time: Option<Duration>) {
+        self.profile.record(syllable, game_mode, outcome, at, response_time);
+        self.session.extend(self.profile.attempts.last().cloned());
+    }
+
+    pub fn card(&self, syllable: &str) -> Option<&Card> {
+        self.profile.card(syllable)
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = self.profile.save() {
+            error!("Failed to save profile '{}': {}", self.profile.name, err);
+        }
+    }
+}
+
+/// Running while answers are not saved yet.
+#[derive(Default)]
+struct PendingSave(Option<Timer>);
+
+pub struct ProgressPlugin;
+
+impl Plugin for ProgressPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ProgressEvent>()
+           .init_resource::<PendingSave>()
+           .add_startup_system(setup)
+           .add_system(progress_system)
+           .add_system(autosave_system)
+           // `AppExit` is sent late in the frame when the window is closed
+           .add_system_to_stage(CoreStage::Last, save_on_exit_system);
+    }
+}
+
+fn setup(progress: Option<Res<Progress>>) {
+    if let Some(progress) = progress {
+        info!("Profile '{}': {} attempt(s) so far", progress.profile.name, progress.profile.attempts.len());
+    }
+}
+
+/// Record the events in the profile, saving it a little after the answers.
+fn progress_system(
+    progress: Option<ResMut<Progress>>,
+    app_state: Res<State<AppState>>,
+    mut pending_save: ResMut<PendingSave>,
+    mut progress_events: EventReader<ProgressEvent>) {
+    let (mut progress, game_mode) = match (progress, app_state.current().game_mode()) {
+        (Some(progress), Some(game_mode)) => (progress, game_mode),
+        _ => {
+            progress_events.clear();
+            return;
+        }
+    };
+
+    let now = SystemTime::now();
+    let mut answered = false;
+
+    for event in progress_events.iter() {
+        let (syllable, outcome, response_time) = match event {
+            ProgressEvent::Heard(syllable) => (syllable, Outcome::Heard, None),
+            ProgressEvent::Typed(syllable) => (syllable, Outcome::Typed, None),
+            ProgressEvent::Answered { syllable, correct, response_time } => {
+                answered = true;
+                (syllable, if *correct { Outcome::Right } else { Outcome::Wrong }, Some(*response_time))
+            }
+        };
+        progress.record(syllable, game_mode, outcome, now, response_time);
+    }
+
+    if answered && pending_save.0.is_none() {
+        pending_save.0 = Some(Timer::from_seconds(SAVE_DELAY, false));
+    }
+}
+
+fn autosave_system(time: Res<Time>, progress: Option<Res<Progress>>, mut pending_save: ResMut<PendingSave>) {
+    let (progress, timer) = match (progress, &mut pending_save.0) {
+        (Some(progress), Some(timer)) => (progress, timer),
+        _ => return
+    };
+
+    if timer.tick(time.delta()).finished() {
+        progress.save();
+        pending_save.0 = None;
+    }
+}
+
+fn save_on_exit_system(progress: Option<Res<Progress>>, mut exit_events: EventReader<AppExit>) {
+    let progress = match progress {
+        Some(progress) if exit_events.iter().next().is_some() => progress,
+        _ => return
+    };
+
+    for (syllable, stats) in progress.profile.syllables.iter() {
+        info!("{}: {}", syllable, stats);
+    }
+    progress.save();
+}
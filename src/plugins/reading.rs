@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+use std::time::Duration;
+
+use crate::{SyllableAudio, SyllablesTree};
+use crate::plugins::{AppState, GameContext, GameMode};
+use crate::plugins::audio::{play_syllable, PlaybackEvent};
+use crate::plugins::exercise::{pick_syllable, Level, FEEDBACK_DELAY};
+use crate::plugins::progress::{Progress, ProgressEvent};
+use crate::plugins::voice::VoiceMenu;
+
+/// Key the parent presses when the syllable was read right.
+const CORRECT_KEY: KeyCode = KeyCode::Return;
+
+/// Key the parent presses to have the syllable read again.
+const TRY_AGAIN_KEY: KeyCode = KeyCode::Back;
+
+#[derive(Default)]
+struct Reading {
+    /// Syllable the player reads aloud, with its recordings.
+    target: Option<(String, SyllableAudio)>,
+    /// When the syllable was shown, in seconds since startup.
+    shown_at: f64,
+    /// Running while the answer is shown and the recording plays.
+    feedback_timer: Option<Timer>,
+    /// The last reading was wrong, the same syllable is shown again.
+    try_again: bool,
+    level: Level
+}
+
+pub struct ReadingPlugin;
+
+impl Plugin for ReadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Reading>()
+           .add_system_set(SystemSet::on_enter(AppState::InGame(GameMode::Reading)).with_system(start_system))
+           .add_system_set(SystemSet::on_update(AppState::InGame(GameMode::Reading))
+                .with_system(next_syllable_system)
+                .with_system(validation_system));
+    }
+}
+
+fn start_system(mut reading: ResMut<Reading>) {
+    reading.target = None;
+    reading.feedback_timer = None;
+    reading.try_again = false;
+}
+
+fn show_syllable(game_ctx: &mut GameContext, syllable: &str) {
+    game_ctx.syllable = syllable.to_string();
+    game_ctx.syllables = vec![syllable.to_string()];
+    game_ctx.completed = 1;
+    game_ctx.feedback = None;
+}
+
+fn next_syllable_system(
+    syllab_tree: Res<SyllablesTree>,
+    progress: Res<Progress>,
+    time: Res<Time>,
+    mut reading: ResMut<Reading>,
+    mut game_ctx: ResMut<GameContext>) {
+    if syllab_tree.is_changed() {
+        reading.target = None;
+        reading.feedback_timer = None;
+        reading.try_again = false;
+    }
+
+    if let Some(timer) = &mut reading.feedback_timer {
+        if !timer.tick(time.delta()).finished() {
+            return;
+        }
+        reading.feedback_timer = None;
+
+        if reading.try_again {
+            if let Some((syllable, _)) = &reading.target {
+                let syllable = syllable.clone();
+                show_syllable(&mut game_ctx, &syllable);
+            }
+            reading.shown_at = time.seconds_since_startup();
+            return;
+        }
+    }
+    else if reading.target.is_some() {
+        return;
+    }
+
+    let previous = reading.target.take().map(|(syllable, _)| syllable);
+    reading.target = pick_syllable(&syllab_tree, &progress, reading.level.level, previous.as_deref());
+    reading.shown_at = time.seconds_since_startup();
+
+    match &reading.target {
+        Some((syllable, _)) => {
+            info!("Reading: '{}'", syllable);
+            show_syllable(&mut game_ctx, syllable);
+        },
+        None => show_syllable(&mut game_ctx, "")
+    }
+}
+
+/// Wait for the parent to tell whether the syllable was read right, then play it.
+fn validation_system(
+    keyboard: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    voice_menu: Res<VoiceMenu>,
+    mut playback_events: EventWriter<PlaybackEvent>,
+    mut reading: ResMut<Reading>,
+    mut game_ctx: ResMut<GameContext>,
+    mut progress_events: EventWriter<ProgressEvent>) {
+    // Return also picks a voice in the menu
+    if voice_menu.open || voice_menu.is_changed() || reading.feedback_timer.is_some() {
+        return;
+    }
+
+    let correct = if keyboard.just_pressed(CORRECT_KEY) {
+        true
+    }
+    else if keyboard.just_pressed(TRY_AGAIN_KEY) {
+        false
+    }
+    else {
+        return;
+    };
+
+    let (syllable, syllable_audio) = match &reading.target {
+        Some(target) => target.clone(),
+        None => return
+    };
+
+    info!("Reading: '{}', {}", syllable, if correct { "right" } else { "try again" });
+
+    play_syllable(&mut playback_events, &syllable_audio);
+    game_ctx.feedback = Some(correct);
+    reading.level.record(correct);
+    reading.try_again = !correct;
+    reading.feedback_timer = Some(Timer::from_seconds(FEEDBACK_DELAY, false));
+    let response_time = Duration::from_secs_f64(time.seconds_since_startup() - reading.shown_at);
+    progress_events.send(ProgressEvent::Answered { syllable, correct, response_time });
+}
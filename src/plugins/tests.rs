@@ -0,0 +1,457 @@
+use bevy::asset::{AssetPlugin, HandleId};
+use bevy::input::ButtonState;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use bevy::time::{create_time_channels, TimeSender};
+use bevy::window::WindowId;
+use clap::ValueEnum;
+use syllabs::voice::{VoicePack, VoicePacks};
+use syllabs::wav::{Wav, WavFormat};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{AudioClip, SyllablesTree};
+use crate::profile::Profile;
+use crate::plugins::{AppState, GameContext, GameMode, InputMethod, InputSettings, Theme, WordListName};
+use crate::plugins::audio::{InternalAudioPlugin, PlaybackEvent};
+use crate::plugins::dictation::DictationPlugin;
+use crate::plugins::input::{InputPlugin, KeyHistory};
+use crate::plugins::menu::MenuPlugin;
+use crate::plugins::progress::{Progress, ProgressPlugin};
+use crate::plugins::voice::{VoiceMenu, VoiceRequest};
+use crate::plugins::words::WordsPlugin;
+use crate::words::{Word, WordList};
+
+const MASH_WINDOW: Duration = Duration::from_secs(1);
+
+/// Time between two keys of a player typing at a steady pace.
+const TYPING_PACE: Duration = Duration::from_millis(400);
+
+/// Time a key is held before the system repeats it, then between the repeats.
+const REPEAT_DELAY: Duration = Duration::from_millis(500);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(33);
+
+/// Clock of the game, only moving forward when told to.
+struct TestClock {
+    sender: TimeSender,
+    now: Instant
+}
+
+/// Syllable of each recording handle of the test voice.
+#[derive(Default)]
+struct Recordings(HashMap<HandleId, String>);
+
+/// Fake sound device: the syllables played so far, in order, and `WORD_SOUND` for the sounds
+/// the game made by joining them.
+#[derive(Default)]
+struct Played(Vec<String>);
+
+const WORD_SOUND: &str = "*";
+
+fn fake_audio_sink_system(recordings: Res<Recordings>, mut played: ResMut<Played>, mut playback_events: EventReader<PlaybackEvent>) {
+    for PlaybackEvent(clip) in playback_events.iter() {
+        played.0.push(recordings.0.get(&clip.handle.id).map_or(WORD_SOUND, String::as_str).to_string());
+    }
+}
+
+/// A headless game in free mode, with a voice having one recording of each of `syllables`.
+fn free_mode_app(syllables: &[&str]) -> App {
+    mashing_app(syllables, 0)
+}
+
+/// `free_mode_app` ignoring the keys while more than `mash_limit` are pressed within a second.
+fn mashing_app(syllables: &[&str], mash_limit: u32) -> App {
+    game_app(syllables, GameMode::Free, InputMethod::Keys, mash_limit)
+}
+
+/// A headless game in words mode asking for `word`, whose recordings are not loaded yet.
+fn words_app(syllables: &[&str], word: &str) -> App {
+    let mut app = game_app(syllables, GameMode::Words, InputMethod::Keys, 0);
+    app.insert_resource(WordList { words: vec![Word::parse(word).unwrap()] });
+    update(&mut app);
+    app
+}
+
+/// Load the recordings of the test voice, as one-sample sounds.
+fn load_recordings(app: &mut App) {
+    // PCM, 1 channel at 8000 Hz, 16 bits
+    let format_chunk = [1, 0, 1, 0, 0x40, 0x1F, 0, 0, 0x80, 0x3E, 0, 0, 2, 0, 16, 0];
+    let format = WavFormat { format_tag: 1, channels: 1, sample_rate: 8000, bits_per_sample: 16 };
+    let wav = Wav { format, format_chunk: &format_chunk, data: &[0, 0] };
+    let bytes: Arc<[u8]> = Wav::concat(&[wav]).unwrap().into();
+
+    let handle_ids: Vec<HandleId> = app.world.resource::<Recordings>().0.keys().copied().collect();
+    let mut audio_sources = app.world.resource_mut::<Assets<AudioSource>>();
+    for handle_id in handle_ids {
+        audio_sources.set_untracked(handle_id, AudioSource { bytes: bytes.clone() });
+    }
+}
+
+/// A headless game in dictation mode, typed with `method`, for a player with no progress yet.
+fn dictation_app(syllables: &[&str], method: InputMethod) -> App {
+    game_app(syllables, GameMode::Dictation, method, 0)
+}
+
+fn game_app(syllables: &[&str], mode: GameMode, method: InputMethod, mash_limit: u32) -> App {
+    let mut syllab_tree = SyllablesTree::new();
+    let mut recordings = Recordings::default();
+    for syllable in syllables {
+        let handle = Handle::<AudioSource>::weak(HandleId::random::<AudioSource>());
+        recordings.0.insert(handle.id, syllable.to_string());
+        syllab_tree.add_syllable(syllable, AudioClip::new(handle));
+    }
+
+    let (sender, receiver) = create_time_channels();
+
+    let mut app = App::new();
+    if mode == GameMode::Dictation {
+        // Unnamed, so never saved
+        app.insert_resource(Progress::new(Profile::default()));
+    }
+    app.insert_resource(receiver)
+       .insert_resource(TestClock { sender, now: Instant::now() })
+       .add_plugins(MinimalPlugins)
+       .add_plugin(AssetPlugin)
+       .add_plugin(bevy::input::InputPlugin)
+       .add_event::<ReceivedCharacter>()
+       .insert_resource(InputSettings {
+            method,
+            mash_limit,
+            mash_window: MASH_WINDOW,
+            layout: "qwerty".to_string()
+        })
+       .insert_resource(GameContext {
+            syllable: String::new(), syllables: Vec::new(), completed: 0, feedback: None })
+       .insert_resource(syllab_tree)
+       .insert_resource(recordings)
+       .init_resource::<VoiceMenu>()
+       .init_resource::<Theme>()
+       .init_resource::<Played>()
+       .add_state(AppState::InGame(mode))
+       .add_plugin(InputPlugin)
+       .add_plugin(ProgressPlugin)
+       .add_plugin(InternalAudioPlugin)
+       .add_plugin(DictationPlugin)
+       .add_system_to_stage(CoreStage::Last, fake_audio_sink_system);
+    if mode == GameMode::Words {
+        app.add_asset::<AudioSource>()
+           .insert_resource(WordListName("fr".to_string()))
+           .add_plugin(WordsPlugin);
+    }
+
+    update(&mut app);
+    app
+}
+
+/// A headless game showing the menu of `state`.
+fn menu_app(state: AppState) -> App {
+    let (sender, receiver) = create_time_channels();
+
+    let mut app = App::new();
+    app.insert_resource(receiver)
+       .insert_resource(TestClock { sender, now: Instant::now() })
+       .add_plugins(MinimalPlugins)
+       .add_plugin(AssetPlugin)
+       .add_plugin(bevy::input::InputPlugin)
+       .add_event::<ReceivedCharacter>()
+       .init_resource::<VoiceMenu>()
+       .init_resource::<Theme>()
+       .add_state(state)
+       .add_plugin(MenuPlugin);
+
+    update(&mut app);
+    app
+}
+
+/// Run a frame at the time of the `TestClock`.
+fn update(app: &mut App) {
+    let clock = app.world.resource::<TestClock>();
+    clock.sender.0.send(clock.now).unwrap();
+    app.update();
+}
+
+fn wait(app: &mut App, duration: Duration) {
+    app.world.resource_mut::<TestClock>().now += duration;
+}
+
+/// Press and release `key_code` right away, then run the frames it takes to be heard.
+fn press(app: &mut App, key_code: KeyCode) {
+    let mut events = app.world.resource_mut::<Events<KeyboardInput>>();
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        events.send(KeyboardInput { scan_code: 0, key_code: Some(key_code), state });
+    }
+
+    update(app);
+    update(app);
+}
+
+/// Press every key of `key_codes` at `TYPING_PACE`.
+fn type_keys(app: &mut App, key_codes: &[KeyCode]) {
+    for key_code in key_codes {
+        wait(app, TYPING_PACE);
+        press(app, *key_code);
+    }
+}
+
+/// Send `key_code` going up or down, with the character `c` it types, and run a frame.
+fn send_key(app: &mut App, key_code: KeyCode, state: ButtonState, c: Option<char>) {
+    if let Some(c) = c {
+        app.world.resource_mut::<Events<ReceivedCharacter>>().send(ReceivedCharacter { id: WindowId::primary(), char: c });
+    }
+    app.world.resource_mut::<Events<KeyboardInput>>().send(KeyboardInput { scan_code: 0, key_code: Some(key_code), state });
+    update(app);
+}
+
+/// Hold `key_code` down as long as the system takes to repeat it, and a second more, the
+/// character `c` coming again with each repeat.
+fn hold_key(app: &mut App, key_code: KeyCode, c: char) {
+    send_key(app, key_code, ButtonState::Pressed, Some(c));
+    wait(app, REPEAT_DELAY);
+    for _ in 0..30 {
+        send_key(app, key_code, ButtonState::Pressed, Some(c));
+        wait(app, REPEAT_INTERVAL);
+    }
+    update(app);
+}
+
+/// Type `c`, a lowercase letter or a space, at `TYPING_PACE`: the key sends the character too,
+/// which `method` tells whether to read.
+fn type_char(app: &mut App, c: char) {
+    let key_code = match c {
+        ' ' => KeyCode::Space,
+        'a' => KeyCode::A,
+        'b' => KeyCode::B,
+        _ => panic!("no key for '{}'", c)
+    };
+    wait(app, TYPING_PACE);
+    app.world.resource_mut::<Events<ReceivedCharacter>>().send(ReceivedCharacter { id: WindowId::primary(), char: c });
+    press(app, key_code);
+}
+
+fn played(app: &App) -> Vec<&str> {
+    app.world.resource::<Played>().0.iter().map(String::as_str).collect()
+}
+
+fn game_ctx(app: &App) -> &GameContext {
+    app.world.resource::<GameContext>()
+}
+
+#[test]
+fn each_letter_plays_the_last_syllable() {
+    let mut app = free_mode_app(&["b", "a", "ba", "t", "to"]);
+    type_keys(&mut app, &[KeyCode::B, KeyCode::A, KeyCode::T, KeyCode::O]);
+
+    assert_eq!(played(&app), ["b", "ba", "t", "to"]);
+    assert_eq!(game_ctx(&app).syllable, "bato");
+    assert_eq!(game_ctx(&app).syllables, ["ba", "to"]);
+    assert_eq!(game_ctx(&app).completed, 2);
+}
+
+#[test]
+fn prefix_of_a_longer_syllable_is_not_complete() {
+    let mut app = free_mode_app(&["ch", "cha", "chat"]);
+    type_keys(&mut app, &[KeyCode::C, KeyCode::H, KeyCode::A]);
+
+    assert_eq!(played(&app), ["ch", "cha"]);
+    assert_eq!(game_ctx(&app).syllables, ["cha"]);
+    assert_eq!(game_ctx(&app).completed, 0);
+}
+
+#[test]
+fn miss_restarts_from_the_last_letter() {
+    let mut app = free_mode_app(&["a", "ba", "to"]);
+    type_keys(&mut app, &[KeyCode::B, KeyCode::A, KeyCode::X]);
+
+    assert_eq!(played(&app), ["ba"]);
+    assert_eq!(game_ctx(&app).syllable, "x");
+    assert_eq!(game_ctx(&app).syllables, ["x"]);
+
+    type_keys(&mut app, &[KeyCode::A]);
+
+    assert_eq!(played(&app), ["ba", "a"]);
+    assert_eq!(game_ctx(&app).syllable, "a");
+}
+
+#[test]
+fn miss_on_a_silent_prefix_plays_nothing() {
+    let mut app = free_mode_app(&["ba", "to"]);
+    type_keys(&mut app, &[KeyCode::B, KeyCode::A, KeyCode::T]);
+
+    assert_eq!(played(&app), ["ba"]);
+    assert_eq!(game_ctx(&app).syllable, "t");
+
+    type_keys(&mut app, &[KeyCode::O]);
+
+    assert_eq!(played(&app), ["ba", "to"]);
+    assert_eq!(game_ctx(&app).syllables, ["to"]);
+}
+
+#[test]
+fn other_keys_clear_the_syllable() {
+    let mut app = free_mode_app(&["ba"]);
+    type_keys(&mut app, &[KeyCode::B, KeyCode::A, KeyCode::Return]);
+
+    assert_eq!(played(&app), ["ba"]);
+    assert!(game_ctx(&app).syllable.is_empty());
+    assert!(game_ctx(&app).syllables.is_empty());
+}
+
+#[test]
+fn quick_presses_of_a_key_are_all_typed() {
+    for method in [InputMethod::Keys, InputMethod::Text] {
+        let mut app = game_app(&["b", "bb"], GameMode::Free, method, 0);
+        for _ in 0..2 {
+            send_key(&mut app, KeyCode::B, ButtonState::Pressed, Some('b'));
+            send_key(&mut app, KeyCode::B, ButtonState::Released, None);
+            wait(&mut app, Duration::from_millis(50));
+        }
+        update(&mut app);
+
+        assert_eq!(played(&app), ["b", "bb"], "{:?}", method);
+        assert_eq!(game_ctx(&app).syllable, "bb", "{:?}", method);
+    }
+}
+
+#[test]
+fn held_key_repeats_are_ignored() {
+    for method in [InputMethod::Keys, InputMethod::Text] {
+        let mut app = game_app(&["b", "bb"], GameMode::Free, method, 0);
+        hold_key(&mut app, KeyCode::B, 'b');
+
+        assert_eq!(played(&app), ["b"], "{:?}", method);
+
+        // Released, the key types again however soon it is pressed
+        send_key(&mut app, KeyCode::B, ButtonState::Released, None);
+        send_key(&mut app, KeyCode::B, ButtonState::Pressed, Some('b'));
+        update(&mut app);
+
+        assert_eq!(played(&app), ["b", "bb"], "{:?}", method);
+    }
+}
+
+#[test]
+fn different_keys_are_not_debounced() {
+    let mut app = free_mode_app(&["b", "ba"]);
+    press(&mut app, KeyCode::B);
+    press(&mut app, KeyCode::A);
+
+    assert_eq!(played(&app), ["b", "ba"]);
+    assert_eq!(game_ctx(&app).syllable, "ba");
+}
+
+fn mashing_hint(app: &mut App) -> String {
+    let mut query = app.world.query::<&Text>();
+    query.iter(&app.world).map(|text| text.sections[0].value.clone()).collect()
+}
+
+#[test]
+fn key_mashing_is_ignored_with_a_hint() {
+    let mut app = mashing_app(&["a", "b", "ba", "c", "d"], 3);
+    for key_code in [KeyCode::B, KeyCode::A, KeyCode::C, KeyCode::D, KeyCode::B] {
+        wait(&mut app, Duration::from_millis(100));
+        press(&mut app, key_code);
+    }
+
+    assert_eq!(played(&app), ["b", "ba", "c"]);
+    assert!(app.world.resource::<KeyHistory>().is_mashing(app.world.resource::<Time>().time_since_startup(), app.world.resource::<InputSettings>()));
+    assert!(!mashing_hint(&mut app).is_empty());
+
+    wait(&mut app, MASH_WINDOW);
+    update(&mut app);
+
+    assert!(mashing_hint(&mut app).is_empty());
+
+    press(&mut app, KeyCode::D);
+
+    assert_eq!(played(&app), ["b", "ba", "c", "d"]);
+}
+
+#[test]
+fn key_mashing_is_allowed_by_default() {
+    let mut app = free_mode_app(&["a", "b", "ba", "c", "d"]);
+    for key_code in [KeyCode::B, KeyCode::A, KeyCode::C, KeyCode::D] {
+        wait(&mut app, Duration::from_millis(50));
+        press(&mut app, key_code);
+    }
+
+    assert_eq!(played(&app), ["b", "ba", "c", "d"]);
+}
+
+#[test]
+fn keys_are_ignored_while_the_voice_menu_is_open() {
+    let mut app = free_mode_app(&["b"]);
+    app.world.resource_mut::<VoiceMenu>().open = true;
+    type_keys(&mut app, &[KeyCode::B]);
+
+    assert!(played(&app).is_empty());
+    assert!(game_ctx(&app).syllable.is_empty());
+}
+
+#[test]
+fn menus_ignore_the_keys_of_the_voice_menu() {
+    let mut app = menu_app(AppState::ModeSelect);
+    app.world.resource_mut::<VoiceMenu>().open = true;
+    type_keys(&mut app, &[KeyCode::Down, KeyCode::Return]);
+    app.world.resource_mut::<VoiceMenu>().open = false;
+    press(&mut app, KeyCode::Escape);
+
+    assert_eq!(*app.world.resource::<State<AppState>>().current(), AppState::ModeSelect);
+
+    type_keys(&mut app, &[KeyCode::Return]);
+    assert_eq!(*app.world.resource::<State<AppState>>().current(), AppState::InGame(GameMode::value_variants()[0]));
+}
+
+#[test]
+fn unknown_voice_falls_back_to_the_first_pack() {
+    let pack = |name: &str, language: &str| VoicePack {
+        name: name.to_string(),
+        path: name.into(),
+        language: Some(language.to_string()),
+        speaker: None,
+        fallback: Vec::new()
+    };
+    let voice_packs = VoicePacks { packs: vec![pack("en", "en"), pack("fr", "fr")] };
+    let resolve = |voice: Option<&str>, language: Option<&str>| {
+        VoiceRequest { voice: voice.map(str::to_string), language: language.map(str::to_string) }.resolve(&voice_packs)
+    };
+
+    assert_eq!(resolve(Some("fr"), None), "fr");
+    assert_eq!(resolve(None, Some("en")), "en");
+    assert_eq!(resolve(None, None), "fr");
+    assert_eq!(resolve(Some("typo"), None), "en");
+    assert_eq!(VoiceRequest { voice: Some("typo".to_string()), language: None }.resolve(&VoicePacks::default()), "typo");
+}
+
+#[test]
+fn whole_word_waits_for_its_recordings() {
+    let mut app = words_app(&["ba", "to"], "ba-to");
+    type_keys(&mut app, &[KeyCode::B, KeyCode::A, KeyCode::T, KeyCode::O]);
+    wait(&mut app, Duration::from_secs(1));
+    update(&mut app);
+    update(&mut app);
+
+    assert_eq!(played(&app), ["ba", "to"]);
+
+    load_recordings(&mut app);
+    update(&mut app);
+    update(&mut app);
+
+    assert_eq!(played(&app), ["ba", "to", WORD_SOUND]);
+}
+
+#[test]
+fn dictation_replay_key_keeps_the_typed_letters() {
+    for method in [InputMethod::Keys, InputMethod::Text] {
+        let mut app = dictation_app(&["ba"], method);
+        update(&mut app);
+        for c in "b a".chars() {
+            type_char(&mut app, c);
+        }
+
+        assert_eq!(played(&app), ["ba", "ba"], "{:?}", method);
+        assert_eq!(game_ctx(&app).syllable, "ba", "{:?}", method);
+        assert_eq!(game_ctx(&app).feedback, Some(true), "{:?}", method);
+    }
+}
@@ -1,55 +1,379 @@
 
 use bevy::prelude::*;
+use bevy::window::WindowResized;
+
+use std::collections::HashMap;
+use std::path::Path;
 
 pub struct TextPlugin;
 
-use crate::plugins::GameContext;
+use crate::plugins::{in_game, GameContext, InGameUi};
+use crate::plugins::theme::Theme;
 
 impl Plugin for TextPlugin  {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup)
-        .add_system(text_update_system);
+        app.init_resource::<DisplayMode>()
+        .add_startup_system(setup)
+        .add_system(text_update_system.with_run_criteria(in_game).label(TextUpdate));
+    }
+}
+
+/// Label of the system writing the syllable text, for the ones changing it afterwards.
+#[derive(SystemLabel, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TextUpdate;
+
+/// Font used when the one of a display mode is missing.
+const DEFAULT_FONT: &str = "fonts/FiraSans-Bold.ttf";
+
+/// How the letters are written, as taught in turn at school.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, clap::ValueEnum)]
+pub enum DisplayMode {
+    /// Lowercase print letters
+    #[default]
+    Lowercase,
+    /// Capital letters
+    Uppercase,
+    /// Joined-up handwriting, with the font <ASSETS>/fonts/cursive.ttf, which is not shipped
+    Cursive,
+    /// Capitals, lowercase and cursive on three lines, for comparison
+    Stacked
+}
+
+impl DisplayMode {
+    /// Modes showing a single line, each with its own font.
+    const LINES: [DisplayMode; 3] = [DisplayMode::Uppercase, DisplayMode::Lowercase, DisplayMode::Cursive];
+
+    /// Modes of the lines shown in this mode, top to bottom.
+    fn lines(self) -> &'static [DisplayMode] {
+        match self {
+            DisplayMode::Lowercase => &DisplayMode::LINES[1..2],
+            DisplayMode::Uppercase => &DisplayMode::LINES[0..1],
+            DisplayMode::Cursive => &DisplayMode::LINES[2..3],
+            DisplayMode::Stacked => &DisplayMode::LINES
+        }
+    }
+
+    fn font_path(self, theme: &Theme) -> &'static str {
+        match self {
+            DisplayMode::Cursive => "fonts/cursive.ttf",
+            _ => theme.print_font.unwrap_or(DEFAULT_FONT)
+        }
+    }
+
+    /// How the lowercase letter `c` is written in this mode.
+    fn write(self, c: char) -> String {
+        match self {
+            DisplayMode::Uppercase => c.to_uppercase().collect(),
+            _ => c.to_string()
+        }
     }
 }
 
+/// Font of each line of the selected `DisplayMode`.
+#[derive(Default)]
+struct DisplayFonts(HashMap<DisplayMode, Handle<Font>>);
+
+impl DisplayFonts {
+    fn get(&self, display_mode: DisplayMode) -> Handle<Font> {
+        self.0.get(&display_mode).cloned().unwrap_or_default()
+    }
+}
+
+/// Share of the window height a single line of text may take.
+const MAX_HEIGHT_SHARE: f32 = 0.42;
+
+/// Share of the window width the text may take.
+const MAX_WIDTH_SHARE: f32 = 0.9;
+
+/// Width of a character relative to the font size, a little more than most letters need.
+const CHAR_WIDTH: f32 = 0.6;
+
+const MIN_FONT_SIZE: f32 = 24.0;
+
 #[derive(Component)]
-struct SyllableText;
+pub struct SyllableText {
+    /// Size the letters are written at, when not animated.
+    pub font_size: f32
+}
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>)
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>, display_mode: Res<DisplayMode>)
 {
     commands.spawn_bundle(Camera2dBundle::default());
 
+    let mut display_fonts = DisplayFonts::default();
+    for display_mode in display_mode.lines().iter().copied() {
+        let mut font_path = display_mode.font_path(&theme);
+        if !asset_server.asset_io().is_file(Path::new(font_path)) {
+            warn!("No font '{}' for {:?} letters, using '{}'", font_path, display_mode, DEFAULT_FONT);
+            font_path = DEFAULT_FONT;
+        }
+        display_fonts.0.insert(display_mode, asset_server.load(font_path));
+    }
+    commands.insert_resource(display_fonts);
+
     let text = TextBundle::from_section(
-        "Hello World",
+        "",
         TextStyle {
-            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font: asset_server.load(DEFAULT_FONT),
             font_size: 300.0,
-            color: Color::WHITE
+            color: theme.text
         }
     )
-    .with_text_alignment(TextAlignment::CENTER)
-    .with_style(Style {
-        position_type: PositionType::Absolute,
-        position: UiRect {
-            left: Val::Px(580.0),
-            top: Val::Px(230.0),
+    .with_text_alignment(TextAlignment::CENTER);
+
+    // Transparent node filling the window, centring the text whatever its size
+    let centering_node = NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
             ..default()
         },
-        align_content: AlignContent::Center,
-        align_items: AlignItems::Center,
-        align_self: AlignSelf::Center,
-        justify_content: JustifyContent::Center,
+        color: Color::NONE.into(),
         ..default()
-    });
+    };
+
+    commands.spawn_bundle(centering_node)
+        .with_children(|parent| {
+            parent.spawn_bundle(text)
+                .insert(SyllableText { font_size: 300.0 })
+                .insert(InGameUi(None));
+        });
+}
+
+/// Largest font size fitting `chars` characters on one line of a `width` by `height` window.
+fn font_size(width: f32, height: f32, chars: usize) -> f32 {
+    let fit_width = width * MAX_WIDTH_SHARE / (chars.max(1) as f32 * CHAR_WIDTH);
+    (height * MAX_HEIGHT_SHARE).min(fit_width).max(MIN_FONT_SIZE)
+}
 
-    commands.spawn()
-        .insert_bundle(text)
-        .insert(SyllableText);
+/// Colour of the letter `c` of the `i`th syllable of `game_ctx`, or of the whole syllable when
+/// its letters are joined up.
+fn color(game_ctx: &GameContext, theme: &Theme, i: usize, c: Option<char>) -> Color {
+    let mut color = match (game_ctx.feedback, c) {
+        (Some(true), _) => theme.right,
+        (Some(false), _) => theme.wrong,
+        (None, Some(c)) => theme.letters.letter(c, theme.text),
+        (None, None) => theme.text
+    };
+    if game_ctx.feedback.is_none() && i >= game_ctx.completed {
+        color.set_a(theme.typing_alpha);
+    }
+    color
 }
 
-fn text_update_system(game_ctx: Res <GameContext>, mut query: Query<&mut Text, With<SyllableText>>) {
-    for mut text in query.iter_mut() {
-        text.sections[0].value = format!("{}", game_ctx.syllable);
-        text.sections[0].style.color = if game_ctx.is_syllable { Color::RED } else { Color::WHITE };
+/// One section per letter of `game_ctx.syllables` on every line of `display_mode`, in the style
+/// of `template` and the colours and spacing of `theme`. Cursive lines get one section per
+/// syllable instead, for the font to join its letters.
+fn letter_sections(
+    game_ctx: &GameContext,
+    theme: &Theme,
+    display_mode: DisplayMode,
+    display_fonts: &DisplayFonts,
+    template: &TextStyle
+) -> Vec<TextSection> {
+    let mut sections = Vec::new();
+    for (line, line_mode) in display_mode.lines().iter().enumerate() {
+        let style = TextStyle { font: display_fonts.get(*line_mode), ..template.clone() };
+        if line > 0 && !game_ctx.syllables.is_empty() {
+            sections.push(TextSection::new("\n", style.clone()));
+        }
+
+        for (i, syllable) in game_ctx.syllables.iter().enumerate() {
+            if i > 0 {
+                sections.push(TextSection::new(theme.syllable_separator, TextStyle { color: theme.faint_text, ..style.clone() }));
+            }
+
+            if *line_mode == DisplayMode::Cursive {
+                let color = color(game_ctx, theme, i, None);
+                sections.push(TextSection::new(syllable.clone(), TextStyle { color, ..style.clone() }));
+                continue;
+            }
+
+            for (j, c) in syllable.chars().enumerate() {
+                if j > 0 && !theme.letter_spacing.is_empty() {
+                    sections.push(TextSection::new(theme.letter_spacing, style.clone()));
+                }
+
+                let color = color(game_ctx, theme, i, Some(c));
+                sections.push(TextSection::new(line_mode.write(c), TextStyle { color, ..style.clone() }));
+            }
+        }
+    }
+
+    if sections.is_empty() {
+        sections.push(TextSection::new("", template.clone()));
+    }
+    sections
+}
+
+fn text_update_system(
+    game_ctx: Res<GameContext>,
+    theme: Res<Theme>,
+    display_mode: Res<DisplayMode>,
+    display_fonts: Res<DisplayFonts>,
+    windows: Res<Windows>,
+    mut resize_events: EventReader<WindowResized>,
+    mut query: Query<(&mut Text, &mut SyllableText)>) {
+    let resized = resize_events.iter().count() > 0;
+    if !game_ctx.is_changed() && !theme.is_changed() && !display_mode.is_changed() && !resized {
+        return;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return
+    };
+
+    for (mut text, mut syllable_text) in query.iter_mut() {
+        let mut sections = letter_sections(&game_ctx, &theme, *display_mode, &display_fonts, &text.sections[0].style);
+
+        let lines = display_mode.lines().len();
+        let chars = sections.iter()
+            .filter(|section| section.value != "\n")
+            .map(|section| section.value.chars().count())
+            .sum::<usize>() / lines;
+        let size = font_size(window.width(), window.height() / lines as f32, chars);
+        for section in sections.iter_mut() {
+            section.style.font_size = size;
+        }
+        syllable_text.font_size = size;
+
+        text.sections = sections;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::HandleId;
+
+    use crate::plugins::theme::AccessibilityPreset;
+
+    fn game_ctx(syllables: &[&str], completed: usize, feedback: Option<bool>) -> GameContext {
+        GameContext {
+            syllable: syllables.concat(),
+            syllables: syllables.iter().map(|syllable| syllable.to_string()).collect(),
+            completed,
+            feedback
+        }
+    }
+
+    fn colors(game_ctx: &GameContext) -> Vec<(String, Color)> {
+        letter_sections(game_ctx, &Theme::default(), DisplayMode::Lowercase, &DisplayFonts::default(), &TextStyle::default()).into_iter()
+            .map(|section| (section.value, section.style.color))
+            .collect()
+    }
+
+    #[test]
+    fn stacked_mode_shows_every_script() {
+        let mut display_fonts = DisplayFonts::default();
+        let fonts: Vec<Handle<Font>> = DisplayMode::LINES.iter().map(|_| Handle::weak(HandleId::random::<Font>())).collect();
+        for (display_mode, font) in DisplayMode::LINES.iter().zip(fonts.iter()) {
+            display_fonts.0.insert(*display_mode, font.clone());
+        }
+
+        let sections = letter_sections(&game_ctx(&["ça", "va"], 2, None), &Theme::default(), DisplayMode::Stacked, &display_fonts, &TextStyle::default());
+        let lines: Vec<(String, Handle<Font>)> = sections.split(|section| section.value == "\n")
+            .map(|line| (line.iter().map(|section| section.value.as_str()).collect(), line[0].style.font.clone()))
+            .collect();
+
+        assert_eq!(lines, [
+            ("ÇA·VA".to_string(), fonts[0].clone()),
+            ("ça·va".to_string(), fonts[1].clone()),
+            ("ça·va".to_string(), fonts[2].clone())
+        ]);
+    }
+
+    #[test]
+    fn cursive_mode_joins_the_letters_of_each_syllable() {
+        let theme = Theme::new(AccessibilityPreset::Dyslexia);
+        let sections = letter_sections(&game_ctx(&["la", "ma"], 1, None), &theme, DisplayMode::Cursive, &DisplayFonts::default(), &TextStyle::default());
+        let mut typing = theme.text;
+        typing.set_a(theme.typing_alpha);
+
+        assert_eq!(sections.iter().map(|section| (section.value.as_str(), section.style.color)).collect::<Vec<_>>(), [
+            ("la", theme.text),
+            (theme.syllable_separator, theme.faint_text),
+            ("ma", typing)
+        ]);
+    }
+
+    #[test]
+    fn uppercase_mode_keeps_the_letter_colours() {
+        let theme = Theme::default();
+        let sections = letter_sections(&game_ctx(&["é"], 1, None), &theme, DisplayMode::Uppercase, &DisplayFonts::default(), &TextStyle::default());
+
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].value, "É");
+        assert_eq!(sections[0].style.color, theme.letters.vowel);
+    }
+
+    #[test]
+    fn dyslexia_preset_spaces_letters_and_syllables() {
+        let theme = Theme::new(AccessibilityPreset::Dyslexia);
+        let sections = letter_sections(&game_ctx(&["la", "ma"], 2, None), &theme, DisplayMode::Lowercase, &DisplayFonts::default(), &TextStyle::default());
+        let text: String = sections.iter().map(|section| section.value.as_str()).collect();
+
+        assert_eq!(text, "l\u{2009}a · m\u{2009}a");
+        assert_eq!(sections[0].style.color, theme.letters.consonant);
+        assert_eq!(sections[2].style.color, theme.letters.vowel);
+    }
+
+    #[test]
+    fn font_fills_the_height_of_short_text() {
+        assert_eq!(font_size(1280.0, 720.0, 2), 720.0 * MAX_HEIGHT_SHARE);
+        assert_eq!(font_size(1920.0, 1080.0, 0), 1080.0 * MAX_HEIGHT_SHARE);
+    }
+
+    #[test]
+    fn font_shrinks_to_fit_long_text() {
+        let size = font_size(1280.0, 720.0, 12);
+
+        assert!(size < 720.0 * MAX_HEIGHT_SHARE);
+        assert!(12.0 * CHAR_WIDTH * size <= 1280.0 * MAX_WIDTH_SHARE);
+        assert!(font_size(1920.0, 1080.0, 12) > size);
+        assert_eq!(font_size(320.0, 200.0, 100), MIN_FONT_SIZE);
+    }
+
+    #[test]
+    fn vowels_and_consonants_have_their_colours() {
+        let theme = Theme::default();
+        let letter_colors = theme.letters;
+        let mut typing = letter_colors.consonant;
+        typing.set_a(theme.typing_alpha);
+
+        assert_eq!(colors(&game_ctx(&["ch", "é"], 2, None)), [
+            ("c".to_string(), letter_colors.consonant),
+            ("h".to_string(), letter_colors.consonant),
+            (theme.syllable_separator.to_string(), theme.faint_text),
+            ("é".to_string(), letter_colors.vowel)
+        ]);
+        assert_eq!(colors(&game_ctx(&["ch"], 0, None)), [("c".to_string(), typing), ("h".to_string(), typing)]);
+    }
+
+    #[test]
+    fn completed_syllables_stay_highlighted() {
+        let theme = Theme::default();
+        let letter_colors = theme.letters;
+        let mut typing = letter_colors.consonant;
+        typing.set_a(theme.typing_alpha);
+
+        assert_eq!(colors(&game_ctx(&["ba", "t"], 1, None)), [
+            ("b".to_string(), letter_colors.consonant),
+            ("a".to_string(), letter_colors.vowel),
+            (theme.syllable_separator.to_string(), theme.faint_text),
+            ("t".to_string(), typing)
+        ]);
+    }
+
+    #[test]
+    fn feedback_and_other_characters() {
+        let theme = Theme::default();
+
+        assert_eq!(colors(&game_ctx(&["?"], 0, None)), [("?".to_string(), *theme.text.clone().set_a(theme.typing_alpha))]);
+        assert_eq!(colors(&game_ctx(&["ba"], 0, Some(false))), [("b".to_string(), theme.wrong), ("a".to_string(), theme.wrong)]);
+        assert_eq!(colors(&game_ctx(&[], 0, None)), [(String::new(), Color::WHITE)]);
     }
 }
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::difficulty::is_vowel;
+
+/// Colours of the Okabe-Ito palette, told apart by people with any form of colour blindness.
+mod okabe_ito {
+    use bevy::prelude::Color;
+
+    pub const ORANGE: Color = Color::rgb(0.9, 0.62, 0.0);
+    pub const SKY_BLUE: Color = Color::rgb(0.34, 0.71, 0.91);
+    pub const BLUISH_GREEN: Color = Color::rgb(0.0, 0.62, 0.45);
+    pub const YELLOW: Color = Color::rgb(0.94, 0.89, 0.26);
+    pub const BLUE: Color = Color::rgb(0.0, 0.45, 0.7);
+    pub const VERMILLION: Color = Color::rgb(0.84, 0.37, 0.0);
+    pub const REDDISH_PURPLE: Color = Color::rgb(0.8, 0.47, 0.65);
+}
+
+/// Colours, font and spacing suiting the needs of the player.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum AccessibilityPreset {
+    /// Light letters on black
+    #[default]
+    Standard,
+    /// Dark letters on off-white, spaced out, in OpenDyslexic when <ASSETS>/fonts/OpenDyslexic-Regular.otf is there
+    Dyslexia,
+    /// Bright letters on black in colours told apart with colour blindness
+    HighContrast
+}
+
+/// Colours letters are shown in, as in classroom reading methods.
+#[derive(Clone, Copy)]
+pub struct LetterColors {
+    pub vowel: Color,
+    pub consonant: Color
+}
+
+impl Default for LetterColors {
+    fn default() -> Self {
+        LetterColors {
+            vowel: Color::rgb(0.95, 0.3, 0.3),
+            consonant: Color::rgb(0.35, 0.65, 1.0)
+        }
+    }
+}
+
+impl LetterColors {
+    /// Colour of `c`, `other` when it is not a letter.
+    pub fn letter(&self, c: char, other: Color) -> Color {
+        if !c.is_alphabetic() {
+            other
+        }
+        else if is_vowel(c) {
+            self.vowel
+        }
+        else {
+            self.consonant
+        }
+    }
+}
+
+/// How the game looks, from an `AccessibilityPreset`.
+pub struct Theme {
+    pub background: Color,
+    /// Menus, hints and the characters that are not letters.
+    pub text: Color,
+    /// Secondary text, such as the word to type and the separators between syllables.
+    pub faint_text: Color,
+    pub letters: LetterColors,
+    /// Letters of a right answer.
+    pub right: Color,
+    /// Letters of a wrong answer.
+    pub wrong: Color,
+    /// Opacity of the letters of the syllable being typed.
+    pub typing_alpha: f32,
+    /// Font of the print letters, instead of the default one.
+    pub print_font: Option<&'static str>,
+    /// Put between the letters of a syllable.
+    pub letter_spacing: &'static str,
+    /// Put between syllables.
+    pub syllable_separator: &'static str
+}
+
+impl Theme {
+    pub fn new(preset: AccessibilityPreset) -> Theme {
+        match preset {
+            AccessibilityPreset::Standard => Theme {
+                background: Color::BLACK,
+                text: Color::WHITE,
+                faint_text: Color::GRAY,
+                letters: LetterColors::default(),
+                right: okabe_ito::BLUISH_GREEN,
+                wrong: okabe_ito::ORANGE,
+                typing_alpha: 0.55,
+                print_font: None,
+                letter_spacing: "",
+                syllable_separator: "·"
+            },
+            AccessibilityPreset::Dyslexia => Theme {
+                background: Color::rgb(0.98, 0.96, 0.9),
+                text: Color::rgb(0.2, 0.2, 0.25),
+                faint_text: Color::rgb(0.55, 0.55, 0.55),
+                letters: LetterColors {
+                    vowel: okabe_ito::VERMILLION,
+                    consonant: okabe_ito::BLUE
+                },
+                right: okabe_ito::BLUISH_GREEN,
+                wrong: okabe_ito::REDDISH_PURPLE,
+                typing_alpha: 0.55,
+                print_font: Some("fonts/OpenDyslexic-Regular.otf"),
+                // Thin spaces
+                letter_spacing: "\u{2009}",
+                syllable_separator: " · "
+            },
+            AccessibilityPreset::HighContrast => Theme {
+                background: Color::BLACK,
+                text: Color::WHITE,
+                faint_text: Color::rgb(0.8, 0.8, 0.8),
+                letters: LetterColors {
+                    vowel: okabe_ito::YELLOW,
+                    consonant: okabe_ito::SKY_BLUE
+                },
+                right: okabe_ito::BLUISH_GREEN,
+                wrong: okabe_ito::ORANGE,
+                typing_alpha: 0.8,
+                print_font: None,
+                letter_spacing: "",
+                syllable_separator: " · "
+            }
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(AccessibilityPreset::Standard)
+    }
+}
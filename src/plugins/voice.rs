@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+use syllabs::voice::{self, VoicePacks, VOICES_FOLDER};
+
+use crate::{Folding, SyllablesTree};
+use crate::plugins::{GameContext, Theme};
+
+/// Voice used when none is given on the command line.
+pub const DEFAULT_VOICE: &str = "fr";
+
+/// Voice asked for on the command line, resolved against the discovered packs at startup.
+pub struct VoiceRequest {
+    pub voice: Option<String>,
+    pub language: Option<String>
+}
+
+impl VoiceRequest {
+    /// Name of the pack to play with: the voice asked for, else the first one speaking the
+    /// language, else `DEFAULT_VOICE`. A voice that was not found falls back to the first pack.
+    pub fn resolve(&self, voice_packs: &VoicePacks) -> String {
+        let language_voice = self.language.as_ref().and_then(|language| {
+            let voice_pack = voice_packs.packs.iter().find(|pack| pack.language.as_ref() == Some(language));
+            if voice_pack.is_none() {
+                warn!("No voice for language '{}'", language);
+            }
+            voice_pack.map(|pack| pack.name.clone())
+        });
+
+        let voice = self.voice.clone()
+            .or(language_voice)
+            .unwrap_or_else(|| DEFAULT_VOICE.to_string());
+
+        match voice_packs.packs.first() {
+            Some(first_pack) if voice_packs.get(&voice).is_none() => {
+                warn!("Unknown voice '{}', using '{}'", voice, first_pack.name);
+                first_pack.name.clone()
+            },
+            _ => voice
+        }
+    }
+}
+
+/// Name of the voice pack syllables are played with. Changing it rebuilds the `SyllablesTree`.
+pub struct SelectedVoice(pub String);
+
+/// In-game list of the voice packs, toggled with F2.
+#[derive(Default)]
+pub struct VoiceMenu {
+    pub open: bool,
+    cursor: usize
+}
+
+pub struct VoicePlugin;
+
+impl Plugin for VoicePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SyllablesTree>()
+           .init_resource::<Folding>()
+           .init_resource::<VoicePacks>()
+           .init_resource::<VoiceMenu>()
+           .add_startup_system(setup)
+           .add_system(load_voice_system)
+           .add_system(voice_menu_input_system)
+           .add_system(voice_menu_text_system);
+    }
+}
+
+#[derive(Component)]
+struct VoiceMenuText;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>, voice_request: Res<VoiceRequest>) {
+    let voice_packs = match VoicePacks::discover(VOICES_FOLDER, asset_server.asset_io()) {
+        Ok(voice_packs) => {
+            info!("Found voices: {}", voice_packs.packs.iter().map(|pack| pack.name.as_str()).collect::<Vec<_>>().join(", "));
+            voice_packs
+        },
+        Err(err) => {
+            error!("Failed to list voices in '{}': {}", VOICES_FOLDER, err);
+            VoicePacks::default()
+        }
+    };
+
+    let voice = voice_request.resolve(&voice_packs);
+    commands.insert_resource(SelectedVoice(voice));
+    commands.insert_resource(voice_packs);
+
+    let text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 40.0,
+            color: theme.text
+        }
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        position: UiRect {
+            left: Val::Px(20.0),
+            top: Val::Px(20.0),
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn()
+        .insert_bundle(text)
+        .insert(VoiceMenuText);
+}
+
+fn load_voice_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    voice_packs: Res<VoicePacks>,
+    selected_voice: Res<SelectedVoice>,
+    folding: Res<Folding>,
+    mut game_ctx: ResMut<GameContext>) {
+    if !selected_voice.is_changed() && !folding.is_changed() {
+        return;
+    }
+
+    let chain = voice_packs.chain(&selected_voice.0);
+    if chain.is_empty() {
+        return;
+    }
+
+    info!("Voice chain: {}", chain.iter().map(|pack| pack.name.as_str()).collect::<Vec<_>>().join(" > "));
+
+    match voice::load_voice_chain(&chain, asset_server.asset_io(), &*asset_server, *folding) {
+        Ok(syllabs_tree) => {
+            info!("{}", syllabs_tree);
+
+            commands.insert_resource(syllabs_tree);
+            game_ctx.clear();
+        },
+        Err(err) => error!("Failed to load voice '{}': {}", selected_voice.0, err)
+    }
+}
+
+fn voice_menu_input_system(
+    keyboard: Res<Input<KeyCode>>,
+    voice_packs: Res<VoicePacks>,
+    mut voice_menu: ResMut<VoiceMenu>,
+    mut selected_voice: ResMut<SelectedVoice>) {
+    if keyboard.just_pressed(KeyCode::F2) {
+        voice_menu.open = !voice_menu.open;
+        voice_menu.cursor = voice_packs.position(&selected_voice.0).unwrap_or(0);
+        return;
+    }
+
+    let count = voice_packs.packs.len();
+    if !voice_menu.open || count == 0 {
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        voice_menu.open = false;
+    }
+    else if keyboard.just_pressed(KeyCode::Up) {
+        voice_menu.cursor = (voice_menu.cursor + count - 1) % count;
+    }
+    else if keyboard.just_pressed(KeyCode::Down) {
+        voice_menu.cursor = (voice_menu.cursor + 1) % count;
+    }
+    else if keyboard.just_pressed(KeyCode::Return) {
+        let name = &voice_packs.packs[voice_menu.cursor].name;
+        if *name != selected_voice.0 {
+            selected_voice.0 = name.clone();
+        }
+        voice_menu.open = false;
+    }
+}
+
+fn voice_menu_text_system(
+    voice_packs: Res<VoicePacks>,
+    voice_menu: Res<VoiceMenu>,
+    mut query: Query<&mut Text, With<VoiceMenuText>>) {
+    if !voice_menu.is_changed() && !voice_packs.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = if voice_menu.open {
+            voice_packs.packs.iter()
+                .enumerate()
+                .map(|(i, pack)| format!("{} {}", if i == voice_menu.cursor { ">" } else { " " }, pack.label()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        else {
+            String::new()
+        };
+    }
+}
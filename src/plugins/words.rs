@@ -0,0 +1,320 @@
+use bevy::asset::LoadState;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use syllabs::wav::Wav;
+use unicode_normalization::UnicodeNormalization;
+
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::{AudioClip, SyllablesTree};
+use crate::plugins::{AppState, GameContext, GameMode, InGameUi, Theme};
+use crate::plugins::audio::{play_syllable, PlaybackEvent};
+use crate::plugins::input::TypingEvent;
+use crate::plugins::progress::ProgressEvent;
+use crate::words::{Word, WordList};
+
+/// Seconds between the last syllable and the whole word.
+const WORD_DELAY: f32 = 0.8;
+
+/// Seconds the whole word stays on screen before the next one.
+const WORD_FEEDBACK_DELAY: f32 = 2.5;
+
+/// Shown between the syllables of the word to type.
+const MODEL_SEPARATOR: &str = "-";
+
+/// Share of the window height above the word to type.
+const MODEL_TOP_SHARE: f32 = 0.08;
+
+/// Name of the word list to load, a file of `WORDS_FOLDER`.
+pub struct WordListName(pub String);
+
+#[derive(Default)]
+struct WordBuilding {
+    word: Option<Word>,
+    /// Index in `word` of the syllable being typed.
+    position: usize,
+    typed: String,
+    /// When the current syllable could first be typed, in seconds since startup.
+    syllable_started_at: f64,
+    /// A wrong letter was typed in the current syllable.
+    mistake: bool,
+    /// The word is complete, it is played whole with these recordings once `timer` finishes and
+    /// they are loaded.
+    word_clips: Option<Vec<AudioClip>>,
+    /// Running once the word is complete.
+    timer: Option<Timer>
+}
+
+pub struct WordsPlugin;
+
+impl Plugin for WordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WordList>()
+           .init_resource::<WordBuilding>()
+           .add_startup_system(setup)
+           .add_system_set(SystemSet::on_enter(AppState::InGame(GameMode::Words)).with_system(start_system))
+           .add_system_set(SystemSet::on_update(AppState::InGame(GameMode::Words))
+                .with_system(next_word_system)
+                .with_system(typing_system)
+                .with_system(model_text_system));
+    }
+}
+
+#[derive(Component)]
+struct ModelText;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>, word_list_name: Res<WordListName>) {
+    match WordList::load(&word_list_name.0, asset_server.asset_io()) {
+        Ok(word_list) => {
+            info!("Word list '{}': {} word(s)", word_list_name.0, word_list.words.len());
+            commands.insert_resource(word_list);
+        },
+        Err(err) => error!("Failed to load word list '{}': {}", word_list_name.0, err)
+    }
+
+    let text = TextBundle::from_section(
+        "",
+        TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 80.0,
+            color: theme.faint_text
+        }
+    )
+    .with_text_alignment(TextAlignment::CENTER);
+
+    // Transparent node filling the window, putting the word centred at the top
+    let top_node = NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            // Columns go upwards, the end is the top of the window
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::FlexEnd,
+            align_items: AlignItems::Center,
+            padding: UiRect { top: Val::Percent(MODEL_TOP_SHARE * 100.0), ..default() },
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    };
+
+    commands.spawn_bundle(top_node)
+        .with_children(|parent| {
+            parent.spawn_bundle(text)
+                .insert(ModelText)
+                .insert(InGameUi(Some(GameMode::Words)));
+        });
+}
+
+fn start_system(mut word_building: ResMut<WordBuilding>) {
+    *word_building = WordBuilding::default();
+}
+
+/// Whether every syllable of `word` has a recording of its own.
+fn can_play(syllab_tree: &SyllablesTree, word: &Word) -> bool {
+    word.syllables.iter().all(|syllable| syllab_tree.get_exact(syllable).is_some())
+}
+
+/// Recordings of the voice, joined into words.
+#[derive(SystemParam)]
+struct WordSounds<'w, 's> {
+    asset_server: Res<'w, AssetServer>,
+    audio_sources: ResMut<'w, Assets<AudioSource>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>
+}
+
+impl<'w, 's> WordSounds<'w, 's> {
+    /// Whether each of `clips` is loaded, or failed to load and never will be.
+    fn loaded(&self, clips: &[AudioClip]) -> bool {
+        clips.iter().all(|clip| {
+            self.audio_sources.contains(&clip.handle) || self.asset_server.get_load_state(&clip.handle) == LoadState::Failed
+        })
+    }
+
+    /// Join `clips`, the recordings of the syllables of `word`, each at its own volume, into a
+    /// new sound.
+    fn join(&mut self, word: &Word, clips: &[AudioClip]) -> Option<Handle<AudioSource>> {
+        let sources = match clips.iter().map(|clip| self.audio_sources.get(&clip.handle)).collect::<Option<Vec<_>>>() {
+            Some(sources) => sources,
+            None => {
+                warn!("Cannot join the syllables of '{}': a recording failed to load", word);
+                return None;
+            }
+        };
+
+        let wavs = match sources.iter().map(|source| Wav::parse(&source.bytes)).collect::<Result<Vec<_>, _>>() {
+            Ok(wavs) => wavs,
+            Err(err) => {
+                warn!("Cannot join the syllables of '{}': {}", word, err);
+                return None;
+            }
+        };
+
+        let amplified = match wavs.iter().zip(clips.iter()).map(|(wav, clip)| wav.amplify(clip.volume)).collect::<Result<Vec<_>, _>>() {
+            Ok(amplified) => amplified,
+            Err(err) => {
+                warn!("Cannot join the syllables of '{}': {}", word, err);
+                return None;
+            }
+        };
+        let wavs: Vec<Wav> = wavs.iter().zip(amplified.iter())
+            .map(|(wav, data)| Wav { format: wav.format, format_chunk: wav.format_chunk, data })
+            .collect();
+
+        match Wav::concat(&wavs) {
+            Ok(bytes) => Some(self.audio_sources.add(AudioSource { bytes: bytes.into() })),
+            Err(err) => {
+                warn!("Cannot join the syllables of '{}': {}", word, err);
+                None
+            }
+        }
+    }
+}
+
+fn show_progress(word_building: &WordBuilding, game_ctx: &mut GameContext) {
+    let mut syllables: Vec<String> = match &word_building.word {
+        Some(word) => word.syllables[..word_building.position].to_vec(),
+        None => Vec::new()
+    };
+    if !word_building.typed.is_empty() {
+        syllables.push(word_building.typed.clone());
+    }
+
+    game_ctx.syllable = syllables.concat();
+    game_ctx.syllables = syllables;
+    game_ctx.completed = word_building.position;
+}
+
+fn next_word_system(
+    syllab_tree: Res<SyllablesTree>,
+    word_list: Res<WordList>,
+    time: Res<Time>,
+    mut playback_events: EventWriter<PlaybackEvent>,
+    mut word_sounds: WordSounds,
+    mut word_building: ResMut<WordBuilding>,
+    mut game_ctx: ResMut<GameContext>) {
+    if syllab_tree.is_changed() || word_list.is_changed() {
+        *word_building = WordBuilding::default();
+    }
+
+    if let Some(timer) = &mut word_building.timer {
+        if !timer.tick(time.delta()).finished() {
+            return;
+        }
+
+        if let (Some(word), Some(word_clips)) = (&word_building.word, &word_building.word_clips) {
+            // Waiting for the recordings of a voice that was just loaded
+            if !word_sounds.loaded(word_clips) {
+                return;
+            }
+
+            if let Some(handle) = word_sounds.join(word, word_clips) {
+                playback_events.send(PlaybackEvent(AudioClip::new(handle)));
+            }
+            word_building.word_clips = None;
+            word_building.timer = Some(Timer::from_seconds(WORD_FEEDBACK_DELAY, false));
+            return;
+        }
+        word_building.timer = None;
+    }
+    else if word_building.word.is_some() {
+        return;
+    }
+
+    let previous = word_building.word.take();
+    let words: Vec<&Word> = word_list.words.iter()
+        .filter(|word| Some(*word) != previous.as_ref() && can_play(&syllab_tree, word))
+        .collect();
+
+    word_building.word = if words.is_empty() { previous } else { Some(words[fastrand::usize(..words.len())].clone()) };
+    word_building.position = 0;
+    word_building.typed.clear();
+    word_building.mistake = false;
+    word_building.syllable_started_at = time.seconds_since_startup();
+
+    if let Some(word) = &word_building.word {
+        info!("Word: '{}'", word);
+    }
+
+    game_ctx.feedback = None;
+    show_progress(&word_building, &mut game_ctx);
+}
+
+/// Accept the letters of the current syllable, play it once complete, then the whole word.
+fn typing_system(
+    syllab_tree: Res<SyllablesTree>,
+    time: Res<Time>,
+    mut playback_events: EventWriter<PlaybackEvent>,
+    mut word_building: ResMut<WordBuilding>,
+    mut game_ctx: ResMut<GameContext>,
+    mut typing_events: EventReader<TypingEvent>,
+    mut progress_events: EventWriter<ProgressEvent>) {
+    for event in typing_events.iter() {
+        if word_building.timer.is_some() {
+            continue;
+        }
+
+        let word = match &word_building.word {
+            Some(word) => word.clone(),
+            None => continue
+        };
+        let syllable = &word.syllables[word_building.position];
+
+        match event {
+            TypingEvent::Char(c) => {
+                let typed: String = word_building.typed.chars().chain([*c]).nfc().collect();
+                if syllab_tree.normalize(syllable).starts_with(&syllab_tree.normalize(&typed)) {
+                    word_building.typed = typed;
+                    game_ctx.feedback = None;
+                }
+                else {
+                    // Wrong letters are not kept, the colour tells the player to try another one
+                    word_building.mistake = true;
+                    game_ctx.feedback = Some(false);
+                }
+            },
+            TypingEvent::Clear => word_building.typed.clear()
+        }
+
+        if syllab_tree.normalize(&word_building.typed) == syllab_tree.normalize(syllable) {
+            if let Some(syllable_audio) = syllab_tree.get_exact(syllable) {
+                play_syllable(&mut playback_events, &syllable_audio);
+            }
+            progress_events.send(ProgressEvent::Typed(syllable.clone()));
+            let response_time = Duration::from_secs_f64(time.seconds_since_startup() - word_building.syllable_started_at);
+            progress_events.send(ProgressEvent::Answered { syllable: syllable.clone(), correct: !word_building.mistake, response_time });
+            word_building.syllable_started_at = time.seconds_since_startup();
+
+            word_building.typed.clear();
+            word_building.mistake = false;
+            word_building.position += 1;
+
+            if word_building.position == word.syllables.len() {
+                info!("Word complete: '{}'", word);
+                word_building.word_clips = Some(word.syllables.iter()
+                    .filter_map(|syllable| syllab_tree.get_exact(syllable))
+                    .map(|syllable_audio| syllable_audio.pick().clone())
+                    .collect());
+                word_building.timer = Some(Timer::from_seconds(WORD_DELAY, false));
+                game_ctx.feedback = Some(true);
+            }
+        }
+
+        show_progress(&word_building, &mut game_ctx);
+    }
+}
+
+fn model_text_system(word_building: Res<WordBuilding>, mut query: Query<&mut Text, With<ModelText>>) {
+    if !word_building.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = match &word_building.word {
+            Some(word) => word.syllables.join(MODEL_SEPARATOR),
+            None => String::new()
+        };
+    }
+}
@@ -0,0 +1,329 @@
+use serde::{Deserialize, Serialize};
+
+use core::fmt;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::plugins::GameMode;
+use crate::scheduler::Card;
+
+/// Profile used when none is given on the command line.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Folder of the user's data directory holding one `<name>.toml` file per profile.
+const PROFILES_FOLDER: &str = "syllabs-rs/profiles";
+
+/// Right and wrong answers kept in a profile, the oldest ones are dropped beyond.
+/// `Profile::syllables` still counts them.
+const MAX_ANSWERS: usize = 2000;
+
+/// Syllables heard or typed in free mode kept in a profile, one per key, so they get their own
+/// limit rather than pushing out the answers.
+const MAX_PLAYED: usize = 500;
+
+#[derive(Debug)]
+pub enum ProfileError {
+    InvalidName(String),
+    NoDataDir,
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Serialize(toml::ser::Error)
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::InvalidName(name) => write!(f, "invalid profile name '{}', use letters, digits, '-' and '_'", name),
+            ProfileError::NoDataDir => write!(f, "no data directory for this user"),
+            ProfileError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ProfileError::Parse(path, err) => write!(f, "{}: invalid profile: {}", path.display(), err),
+            ProfileError::Serialize(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl From<toml::ser::Error> for ProfileError {
+    fn from(err: toml::ser::Error) -> Self {
+        ProfileError::Serialize(err)
+    }
+}
+
+/// What happened to a syllable during an attempt.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Heard,
+    Typed,
+    Right,
+    Wrong
+}
+
+impl Outcome {
+    /// Whether the player was asked for the syllable, rather than playing freely.
+    pub fn is_answer(self) -> bool {
+        matches!(self, Outcome::Right | Outcome::Wrong)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Attempt {
+    pub syllable: String,
+    pub mode: GameMode,
+    pub outcome: Outcome,
+    #[serde(with = "humantime_serde")]
+    pub at: SystemTime,
+    /// Time the player took to answer, for right and wrong outcomes.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "humantime_serde")]
+    pub response_time: Option<Duration>
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(default)]
+pub struct SyllableStats {
+    pub heard: u32,
+    pub typed: u32,
+    pub correct: u32,
+    pub wrong: u32,
+    /// Right answers in a row, up to the last answer.
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Sum of the response times of the `timed` answers that have one.
+    #[serde(with = "humantime_serde")]
+    pub response_time: Duration,
+    pub timed: u32
+}
+
+impl fmt::Display for SyllableStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "heard {}, typed {}, right {}, wrong {}", self.heard, self.typed, self.correct, self.wrong)
+    }
+}
+
+/// Progress of one player, kept across sessions in `<data dir>/<PROFILES_FOLDER>/<name>.toml`.
+///
+/// ```toml
+/// name = "lea"
+///
+/// [syllables.ba]
+/// heard = 3
+/// correct = 2
+/// streak = 2
+/// best_streak = 2
+/// response_time = "4s 100ms"
+/// timed = 2
+///
+/// [cards.ba]
+/// box = 2
+/// due = "2022-10-01T09:40:00Z"
+///
+/// [[attempts]]
+/// syllable = "ba"
+/// mode = "dictation"
+/// outcome = "right"
+/// at = "2022-10-01T09:30:00Z"
+/// response_time = "2s 300ms"
+/// ```
+#[derive(Serialize, Deserialize, Default)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub syllables: BTreeMap<String, SyllableStats>,
+    /// Leitner boxes of the syllables answered at least once.
+    #[serde(default)]
+    pub cards: BTreeMap<String, Card>,
+    /// The last `MAX_ANSWERS` answers and `MAX_PLAYED` syllables heard or typed.
+    #[serde(default)]
+    pub attempts: Vec<Attempt>
+}
+
+fn profiles_dir() -> Result<PathBuf, ProfileError> {
+    let data_dir = dirs::data_dir().ok_or(ProfileError::NoDataDir)?;
+    Ok(data_dir.join(PROFILES_FOLDER))
+}
+
+impl Profile {
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    }
+
+    pub fn path(name: &str) -> Result<PathBuf, ProfileError> {
+        if !Profile::is_valid_name(name) {
+            return Err(ProfileError::InvalidName(name.to_string()));
+        }
+
+        Ok(profiles_dir()?.join(format!("{}.toml", name)))
+    }
+
+    /// Names of the saved profiles, in alphabetical order.
+    pub fn list() -> Result<Vec<String>, ProfileError> {
+        let dir = profiles_dir()?;
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(ProfileError::Io(dir, err))
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string))
+            .filter(|name| Profile::is_valid_name(name))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Load the profile `name`, or start a new one if it was never saved.
+    pub fn load(name: &str) -> Result<Profile, ProfileError> {
+        let path = Profile::path(name)?;
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let mut profile: Profile = toml::from_slice(&bytes).map_err(|err| ProfileError::Parse(path, err))?;
+                // The file name wins, so that a copied profile is saved where it was loaded from
+                profile.name = name.to_string();
+                profile.compact();
+                Ok(profile)
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Profile {
+                name: name.to_string(),
+                ..Profile::default()
+            }),
+            Err(err) => Err(ProfileError::Io(path, err))
+        }
+    }
+
+    /// Write the profile, through a temporary file so that a crash never leaves half of it.
+    pub fn save(&self) -> Result<(), ProfileError> {
+        let path = Profile::path(&self.name)?;
+        let content = toml::to_string(self)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| ProfileError::Io(parent.to_path_buf(), err))?;
+        }
+
+        let tmp_path = path.with_extension("toml.tmp");
+        fs::write(&tmp_path, content).map_err(|err| ProfileError::Io(tmp_path.clone(), err))?;
+        fs::rename(&tmp_path, &path).map_err(|err| ProfileError::Io(path, err))
+    }
+
+    /// Drop the oldest answers beyond `MAX_ANSWERS` and the oldest syllables played beyond
+    /// `MAX_PLAYED`, so that the file stays small.
+    fn compact(&mut self) {
+        let mut answers = self.attempts.iter().filter(|attempt| attempt.outcome.is_answer()).count();
+        let mut played = self.attempts.len() - answers;
+        if answers <= MAX_ANSWERS && played <= MAX_PLAYED {
+            return;
+        }
+
+        self.attempts.sort_by_key(|attempt| attempt.at);
+        self.attempts.retain(|attempt| {
+            let (count, max) = if attempt.outcome.is_answer() { (&mut answers, MAX_ANSWERS) } else { (&mut played, MAX_PLAYED) };
+            if *count > max {
+                *count -= 1;
+                false
+            }
+            else {
+                true
+            }
+        });
+    }
+
+    pub fn card(&self, syllable: &str) -> Option<&Card> {
+        self.cards.get(syllable)
+    }
+
+    /// Count `outcome` for `syllable` and log it, at the second. Answers also move the
+    /// syllable between the Leitner boxes.
+    pub fn record(&mut self, syllable: &str, mode: GameMode, outcome: Outcome, at: SystemTime, response_time: Option<Duration>) {
+        let at = UNIX_EPOCH + Duration::from_secs(at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs());
+
+        if outcome.is_answer() {
+            self.cards.entry(syllable.to_string())
+                .or_insert_with(|| Card::new(at))
+                .review(outcome == Outcome::Right, at);
+        }
+
+        // Milliseconds are plenty and keep the file readable
+        let response_time = response_time.map(|duration| Duration::from_millis(duration.as_millis() as u64));
+
+        let stats = self.syllables.entry(syllable.to_string()).or_default();
+        match outcome {
+            Outcome::Heard => stats.heard += 1,
+            Outcome::Typed => stats.typed += 1,
+            Outcome::Right => {
+                stats.correct += 1;
+                stats.streak += 1;
+                stats.best_streak = stats.best_streak.max(stats.streak);
+            },
+            Outcome::Wrong => {
+                stats.wrong += 1;
+                stats.streak = 0;
+            }
+        }
+        if let Some(response_time) = response_time {
+            stats.response_time += response_time;
+            stats.timed += 1;
+        }
+
+        self.attempts.push(Attempt {
+            syllable: syllable.to_string(),
+            mode,
+            outcome,
+            at,
+            response_time
+        });
+        self.compact();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(profile: &mut Profile, correct: bool, seconds: u64) {
+        let outcome = if correct { Outcome::Right } else { Outcome::Wrong };
+        profile.record("ba", GameMode::Dictation, outcome, UNIX_EPOCH + Duration::from_secs(seconds), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn stats_keep_streaks_and_response_times() {
+        let mut profile = Profile::default();
+        for (i, correct) in [true, true, false, true].into_iter().enumerate() {
+            answer(&mut profile, correct, i as u64);
+        }
+        let stats = profile.syllables["ba"];
+
+        assert_eq!((stats.correct, stats.wrong), (3, 1));
+        assert_eq!((stats.streak, stats.best_streak), (1, 2));
+        assert_eq!((stats.response_time, stats.timed), (Duration::from_secs(6), 4));
+    }
+
+    #[test]
+    fn record_keeps_the_latest_attempts() {
+        let mut profile = Profile::default();
+        for i in 0..MAX_ANSWERS + 10 {
+            answer(&mut profile, true, i as u64);
+        }
+
+        assert_eq!(profile.attempts.len(), MAX_ANSWERS);
+        assert_eq!(profile.attempts[0].at, UNIX_EPOCH + Duration::from_secs(10));
+        assert_eq!(profile.syllables["ba"].correct as usize, MAX_ANSWERS + 10);
+    }
+
+    #[test]
+    fn syllables_played_do_not_push_out_the_answers() {
+        let mut profile = Profile::default();
+        answer(&mut profile, true, 0);
+        for i in 0..MAX_PLAYED + 10 {
+            profile.record("ba", GameMode::Free, Outcome::Typed, UNIX_EPOCH + Duration::from_secs(1 + i as u64), None);
+        }
+
+        assert_eq!(profile.attempts.len(), MAX_PLAYED + 1);
+        assert_eq!(profile.attempts[0].outcome, Outcome::Right);
+        assert_eq!(profile.attempts[1].at, UNIX_EPOCH + Duration::from_secs(11));
+    }
+}
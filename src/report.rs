@@ -0,0 +1,362 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use crate::difficulty::is_vowel;
+use crate::profile::Profile;
+
+/// Families answered at least this many times are judged.
+const MIN_FAMILY_ANSWERS: u32 = 3;
+
+/// Families with a lower accuracy are weak.
+const WEAK_ACCURACY: f32 = 0.7;
+
+/// What a profile did with one syllable.
+#[derive(Default)]
+pub struct SyllableReport {
+    pub heard: u32,
+    pub typed: u32,
+    pub right: u32,
+    pub wrong: u32,
+    pub best_streak: u32,
+    pub current_streak: u32,
+    /// Sum of the response times of the `timed` answers that have one.
+    response_time: Duration,
+    timed: u32,
+    pub leitner_box: Option<u32>
+}
+
+impl SyllableReport {
+    pub fn answers(&self) -> u32 {
+        self.right + self.wrong
+    }
+
+    pub fn accuracy(&self) -> Option<f32> {
+        if self.answers() == 0 { None } else { Some(self.right as f32 / self.answers() as f32) }
+    }
+
+    pub fn mean_response_time(&self) -> Option<Duration> {
+        if self.timed == 0 { None } else { Some(self.response_time / self.timed) }
+    }
+}
+
+/// Right and wrong answers over every syllable sharing a consonant onset, as in "b-", or a
+/// vowel ending, as in "-on".
+#[derive(Default)]
+pub struct FamilyReport {
+    pub right: u32,
+    pub wrong: u32
+}
+
+impl FamilyReport {
+    pub fn accuracy(&self) -> Option<f32> {
+        let answers = self.right + self.wrong;
+        if answers == 0 { None } else { Some(self.right as f32 / answers as f32) }
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.right + self.wrong >= MIN_FAMILY_ANSWERS && self.accuracy().is_some_and(|accuracy| accuracy < WEAK_ACCURACY)
+    }
+}
+
+pub struct Report {
+    pub profile: String,
+    pub syllables: BTreeMap<String, SyllableReport>,
+    pub families: BTreeMap<String, FamilyReport>
+}
+
+/// Families of `syllable`: its leading consonants and what follows them. Either is left out
+/// when empty.
+fn families(syllable: &str) -> Vec<String> {
+    let onset_len = syllable.chars().take_while(|c| !is_vowel(*c)).count();
+    let onset: String = syllable.chars().take(onset_len).collect();
+    let rest: String = syllable.chars().skip(onset_len).collect();
+
+    let mut families = Vec::new();
+    if !onset.is_empty() && !rest.is_empty() {
+        families.push(format!("{}-", onset));
+    }
+    if !rest.is_empty() {
+        families.push(format!("-{}", rest));
+    }
+    families
+}
+
+impl Report {
+    pub fn new(profile: &Profile) -> Report {
+        let mut syllables: BTreeMap<String, SyllableReport> = profile.syllables.iter()
+            .map(|(syllable, stats)| (syllable.clone(), SyllableReport {
+                heard: stats.heard,
+                typed: stats.typed,
+                right: stats.correct,
+                wrong: stats.wrong,
+                best_streak: stats.best_streak,
+                current_streak: stats.streak,
+                response_time: stats.response_time,
+                timed: stats.timed,
+                leitner_box: None
+            }))
+            .collect();
+
+        let mut families: BTreeMap<String, FamilyReport> = BTreeMap::new();
+        for (syllable, report) in syllables.iter_mut() {
+            report.leitner_box = profile.cards.get(syllable).map(|card| card.box_number);
+
+            if report.answers() > 0 {
+                for family in self::families(syllable) {
+                    let family_report = families.entry(family).or_default();
+                    family_report.right += report.right;
+                    family_report.wrong += report.wrong;
+                }
+            }
+        }
+
+        Report {
+            profile: profile.name.clone(),
+            syllables,
+            families
+        }
+    }
+
+    pub fn weak_families(&self) -> Vec<&str> {
+        self.families.iter()
+            .filter(|(_, family)| family.is_weak())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("syllable,answers,right,wrong,accuracy,mean_response_ms,best_streak,current_streak,heard,typed,box\n");
+        for (syllable, report) in self.syllables.iter() {
+            let _ = writeln!(csv, "{},{},{},{},{},{},{},{},{},{},{}",
+                csv_field(syllable),
+                report.answers(),
+                report.right,
+                report.wrong,
+                report.accuracy().map(|accuracy| format!("{:.2}", accuracy)).unwrap_or_default(),
+                report.mean_response_time().map(|duration| duration.as_millis().to_string()).unwrap_or_default(),
+                report.best_streak,
+                report.current_streak,
+                report.heard,
+                report.typed,
+                report.leitner_box.map(|leitner_box| leitner_box.to_string()).unwrap_or_default());
+        }
+        csv
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Syllabs - {}</title>", html_escape(&self.profile));
+        html.push_str("<style>\n\
+            body { font-family: sans-serif; margin: 2em; }\n\
+            table { border-collapse: collapse; margin-bottom: 2em; }\n\
+            th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: right; }\n\
+            th:first-child, td:first-child { text-align: left; }\n\
+            .weak { background: #fde2e1; }\n\
+            </style>\n</head>\n<body>\n");
+        let _ = writeln!(html, "<h1>Progress of {}</h1>", html_escape(&self.profile));
+
+        html.push_str("<h2>Weak families</h2>\n");
+        let weak_families = self.weak_families();
+        if weak_families.is_empty() {
+            html.push_str("<p>None so far.</p>\n");
+        }
+        else {
+            let _ = writeln!(html, "<p>{}</p>", weak_families.iter().map(|family| html_escape(family)).collect::<Vec<_>>().join(", "));
+        }
+
+        html.push_str("<h2>Families</h2>\n<table>\n<tr><th>Family</th><th>Right</th><th>Wrong</th><th>Accuracy</th></tr>\n");
+        for (name, family) in self.families.iter() {
+            let _ = writeln!(html, "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                if family.is_weak() { " class=\"weak\"" } else { "" },
+                html_escape(name),
+                family.right,
+                family.wrong,
+                percent(family.accuracy()));
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Syllables</h2>\n<table>\n<tr><th>Syllable</th><th>Answers</th><th>Accuracy</th><th>Time to answer</th>\
+            <th>Best streak</th><th>Current streak</th><th>Heard</th><th>Typed</th><th>Box</th></tr>\n");
+        for (syllable, report) in self.syllables.iter() {
+            let _ = writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                html_escape(syllable),
+                report.answers(),
+                percent(report.accuracy()),
+                report.mean_response_time().map(|duration| format!("{:.1} s", duration.as_secs_f32())).unwrap_or_default(),
+                report.best_streak,
+                report.current_streak,
+                report.heard,
+                report.typed,
+                report.leitner_box.map(|leitner_box| leitner_box.to_string()).unwrap_or_default());
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn percent(accuracy: Option<f32>) -> String {
+    accuracy.map(|accuracy| format!("{:.0}%", accuracy * 100.0)).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+    else {
+        value.to_string()
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Write `<profile>-report.csv` and `<profile>-report.html` in `output_dir`.
+pub fn run(profile_name: Option<&str>, output_dir: &Path) -> ExitCode {
+    let profile_name = match profile_name {
+        Some(profile_name) => profile_name,
+        None => {
+            eprintln!("error: the report needs a profile, give it with --profile <NAME>");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Loading a profile that was never saved gives an empty one, not worth a report
+    match Profile::path(profile_name) {
+        Ok(path) if !path.is_file() => {
+            eprintln!("error: no profile '{}' in {}", profile_name, path.parent().unwrap_or(&path).display());
+            return ExitCode::FAILURE;
+        },
+        Ok(_) => {},
+        Err(err) => {
+            eprintln!("error: profile '{}': {}", profile_name, err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let profile = match Profile::load(profile_name) {
+        Ok(profile) => profile,
+        Err(err) => {
+            eprintln!("error: profile '{}': {}", profile_name, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if profile.attempts.is_empty() {
+        eprintln!("warning: profile '{}' has no saved session", profile_name);
+    }
+
+    let report = Report::new(&profile);
+    let files: [(PathBuf, String); 2] = [
+        (output_dir.join(format!("{}-report.csv", profile_name)), report.to_csv()),
+        (output_dir.join(format!("{}-report.html", profile_name)), report.to_html())
+    ];
+
+    if let Err(err) = fs::create_dir_all(output_dir) {
+        eprintln!("error: {}: {}", output_dir.display(), err);
+        return ExitCode::FAILURE;
+    }
+
+    for (path, content) in files.iter() {
+        if let Err(err) = fs::write(path, content) {
+            eprintln!("error: {}: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+        println!("Wrote {}", path.display());
+    }
+
+    let weak_families = report.weak_families();
+    if !weak_families.is_empty() {
+        println!("Weak families: {}", weak_families.join(", "));
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::plugins::GameMode;
+    use crate::profile::Outcome;
+
+    /// A profile answering each syllable of `answers`, right or wrong, a second apart.
+    fn profile(answers: &[(&str, bool)]) -> Profile {
+        let mut profile = Profile { name: "lea".to_string(), ..Profile::default() };
+        for (i, (syllable, correct)) in answers.iter().enumerate() {
+            let outcome = if *correct { Outcome::Right } else { Outcome::Wrong };
+            let at: SystemTime = UNIX_EPOCH + Duration::from_secs(i as u64);
+            profile.record(syllable, GameMode::Dictation, outcome, at, Some(Duration::from_millis(1000 + 1000 * i as u64)));
+        }
+        profile
+    }
+
+    #[test]
+    fn streaks_of_right_answers() {
+        let report = Report::new(&profile(&[("ba", true), ("ba", true), ("ba", true), ("ba", false), ("ba", true)]));
+        let ba = &report.syllables["ba"];
+
+        assert_eq!((ba.right, ba.wrong, ba.answers()), (4, 1, 5));
+        assert_eq!(ba.best_streak, 3);
+        assert_eq!(ba.current_streak, 1);
+        assert_eq!(ba.accuracy(), Some(0.8));
+    }
+
+    #[test]
+    fn mean_response_time() {
+        let report = Report::new(&profile(&[("ba", true), ("to", true), ("ba", false)]));
+
+        assert_eq!(report.syllables["ba"].mean_response_time(), Some(Duration::from_secs(2)));
+        assert_eq!(report.syllables["to"].mean_response_time(), Some(Duration::from_secs(2)));
+        assert_eq!(SyllableReport::default().mean_response_time(), None);
+    }
+
+    #[test]
+    fn syllables_grouped_by_onset_and_rime() {
+        assert_eq!(families("ba"), ["b-", "-a"]);
+        assert_eq!(families("chon"), ["ch-", "-on"]);
+        assert_eq!(families("an"), ["-an"]);
+        assert!(families("ch").is_empty());
+
+        let report = Report::new(&profile(&[("ba", true), ("bo", false), ("ta", true)]));
+        let family = |name: &str| (report.families[name].right, report.families[name].wrong);
+
+        assert_eq!(family("b-"), (1, 1));
+        assert_eq!(family("-a"), (2, 0));
+        assert_eq!(family("-o"), (0, 1));
+        assert_eq!(family("t-"), (1, 0));
+    }
+
+    #[test]
+    fn weak_families_need_enough_answers() {
+        let report = Report::new(&profile(&[
+            // 2 out of 3, weak
+            ("ba", true), ("bo", true), ("bi", false),
+            // 0 out of 2, too few answers
+            ("mu", false), ("mu", false),
+            // 7 out of 10, not below the threshold
+            ("ta", true), ("ta", true), ("ta", true), ("ta", true), ("ta", true), ("ta", true), ("ta", true),
+            ("ta", false), ("ta", false), ("ta", false)
+        ]));
+
+        assert!(report.families["b-"].is_weak());
+        assert!(!report.families["m-"].is_weak());
+        assert!(!report.families["t-"].is_weak());
+        assert_eq!(report.weak_families(), ["b-"]);
+    }
+
+    #[test]
+    fn unknown_profile_writes_no_report() {
+        let name = format!("no-such-profile-{}", std::process::id());
+        let output_dir = std::env::temp_dir().join(format!("syllabs-report-{}", std::process::id()));
+
+        assert_eq!(run(Some(&name), &output_dir), ExitCode::FAILURE);
+        assert!(!output_dir.exists());
+    }
+}
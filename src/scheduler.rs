@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use std::time::{Duration, SystemTime};
+
+const MINUTE: u64 = 60;
+const DAY: u64 = 24 * 60 * MINUTE;
+
+/// Delay before a syllable is asked again, for each Leitner box. A missed syllable goes back to
+/// the first box and comes back in the same session, a known one climbs a box each time.
+const BOX_INTERVALS: [Duration; 5] = [
+    Duration::from_secs(0),
+    Duration::from_secs(10 * MINUTE),
+    Duration::from_secs(DAY),
+    Duration::from_secs(3 * DAY),
+    Duration::from_secs(7 * DAY)
+];
+
+/// Number of Leitner boxes.
+pub const BOXES: u32 = BOX_INTERVALS.len() as u32;
+
+/// Where a syllable stands in the Leitner boxes.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Card {
+    /// From 1, just missed, to `BOXES`, mastered.
+    #[serde(rename = "box")]
+    pub box_number: u32,
+    #[serde(with = "humantime_serde")]
+    pub due: SystemTime
+}
+
+impl Card {
+    pub fn new(now: SystemTime) -> Card {
+        Card {
+            box_number: 1,
+            due: now
+        }
+    }
+
+    /// Move the card up a box on a right answer, back to the first one otherwise.
+    pub fn review(&mut self, correct: bool, now: SystemTime) {
+        self.box_number = if correct { (self.box_number + 1).min(BOXES) } else { 1 };
+        self.due = now + BOX_INTERVALS[self.box_number as usize - 1];
+    }
+
+    pub fn is_due(&self, now: SystemTime) -> bool {
+        self.due <= now
+    }
+}
+
+/// How much a syllable should be asked now, `None` when it is not due. Each box down doubles
+/// it, and syllables never asked count as much as the ones in the last box.
+pub fn priority(card: Option<&Card>, now: SystemTime) -> Option<f32> {
+    match card {
+        Some(card) if !card.is_due(now) => None,
+        Some(card) => Some(2f32.powi((BOXES - card.box_number.clamp(1, BOXES)) as i32)),
+        None => Some(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::UNIX_EPOCH;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn card(box_number: u32, due: SystemTime) -> Card {
+        Card { box_number, due }
+    }
+
+    #[test]
+    fn right_answers_climb_one_box_at_a_time() {
+        let mut card = Card::new(at(0));
+        let mut boxes = Vec::new();
+        for _ in 0..BOXES + 1 {
+            card.review(true, at(0));
+            boxes.push(card.box_number);
+        }
+
+        assert_eq!(boxes, [2, 3, 4, 5, 5, 5]);
+    }
+
+    #[test]
+    fn wrong_answer_goes_back_to_the_first_box() {
+        let mut card = card(4, at(0));
+        card.review(false, at(100));
+
+        assert_eq!(card.box_number, 1);
+        assert_eq!(card.due, at(100));
+        assert!(card.is_due(at(100)));
+    }
+
+    #[test]
+    fn due_after_the_interval_of_the_box() {
+        let mut card = Card::new(at(0));
+        card.review(true, at(1000));
+
+        assert_eq!(card.due, at(1000 + 10 * MINUTE));
+        assert!(!card.is_due(at(1000 + 10 * MINUTE - 1)));
+        assert!(card.is_due(at(1000 + 10 * MINUTE)));
+
+        card.review(true, at(2000));
+        assert_eq!(card.due, at(2000 + DAY));
+    }
+
+    #[test]
+    fn lower_boxes_come_first() {
+        let now = at(DAY);
+        let mut cards = [card(3, at(0)), card(1, at(0)), card(5, at(0)), card(2, at(0))];
+        cards.sort_by(|a, b| priority(Some(b), now).partial_cmp(&priority(Some(a), now)).unwrap());
+        let boxes: Vec<u32> = cards.iter().map(|card| card.box_number).collect();
+
+        assert_eq!(boxes, [1, 2, 3, 5]);
+        assert_eq!(priority(Some(&card(1, at(0))), now), Some(16.0));
+        assert_eq!(priority(None, now), priority(Some(&card(BOXES, at(0))), now));
+    }
+
+    #[test]
+    fn cards_not_due_are_left_out() {
+        assert_eq!(priority(Some(&card(1, at(DAY + 1))), at(DAY)), None);
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(feature = "bevy")]
+use bevy::asset::{AssetIo, AssetIoError};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Files the voice packs are read from.
+///
+/// Paths are relative to the root of the source, and so are the ones returned by `read_dir`.
+pub trait FileSource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Paths of the entries of the directory `path`.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+}
+
+/// Files under a directory of the file system.
+pub struct Directory(pub PathBuf);
+
+impl FileSource for Directory {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(self.0.join(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.0.join(path).is_dir()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(self.0.join(path))?
+            .map(|entry| entry.map(|entry| path.join(entry.file_name())))
+            .collect()
+    }
+}
+
+#[cfg(feature = "bevy")]
+impl FileSource for dyn AssetIo {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        futures_lite::future::block_on(self.load_path(path)).map_err(into_io_error)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        AssetIo::is_dir(self, path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(self.read_directory(path).map_err(into_io_error)?.collect())
+    }
+}
+
+#[cfg(feature = "bevy")]
+fn into_io_error(err: AssetIoError) -> io::Error {
+    match err {
+        AssetIoError::NotFound(path) => io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("path not found: {}", path.display())
+        ),
+        AssetIoError::Io(err) => err,
+        err => io::Error::other(err.to_string())
+    }
+}
@@ -0,0 +1,607 @@
+use log::trace;
+use indextree::{Arena, NodeEdge, NodeId};
+use unicode_normalization::UnicodeNormalization;
+
+use core::fmt;
+use std::collections::HashSet;
+use std::str::Chars;
+
+/// Character stored in the root node, which only anchors the first letter of every syllable.
+const ROOT_CHAR: char = '\0';
+
+/// A recording of a syllable, played at `volume`.
+#[derive(Clone)]
+pub struct AudioClip<H> {
+    pub handle: H,
+    pub volume: f32
+}
+
+impl<H> AudioClip<H> {
+    pub fn new(handle: H) -> AudioClip<H> {
+        AudioClip {
+            handle,
+            volume: 1.0
+        }
+    }
+}
+
+/// Every recording available for one syllable.
+#[derive(Clone)]
+pub struct SyllableAudio<H> {
+    clips: Vec<AudioClip<H>>
+}
+
+impl<H> SyllableAudio<H> {
+    /// Pick one of the recordings at random, so that a syllable with several takes does not
+    /// always sound the same.
+    pub fn pick(&self) -> &AudioClip<H> {
+        &self.clips[fastrand::usize(..self.clips.len())]
+    }
+}
+
+struct SyllableAtom<H> {
+    audio: Option<SyllableAudio<H>>,
+    /// `audio` was recorded for a spelling that `Folding::IgnoreAccents` changed.
+    folded_audio: bool,
+    c: char
+}
+
+impl<H> SyllableAtom<H> {
+    pub fn new(c: char) -> SyllableAtom<H> {
+        SyllableAtom {
+            audio: None,
+            folded_audio: false,
+            c
+        }
+    }
+
+    pub fn char(&self) -> char {
+        self.c
+    }
+
+    pub fn add_clip(&mut self, clip: AudioClip<H>)
+    {
+        match &mut self.audio {
+            Some(audio) => audio.clips.push(clip),
+            None => self.audio = Some(SyllableAudio { clips: vec![clip] })
+        }
+    }
+}
+
+/// A syllable found by `SyllablesTree::segment`.
+pub struct Segment<H> {
+    pub syllable: String,
+    pub audio: SyllableAudio<H>,
+    /// `true` when no longer syllable starts with this one.
+    pub is_leaf: bool
+}
+
+/// How spellings are compared. Either way they are put in NFC and lowercased, so that a
+/// decomposed "é" or a capital letter matches what the player types.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Folding {
+    #[default]
+    Exact,
+    /// Accented vowels match the plain ones, for beginners.
+    IgnoreAccents
+}
+
+impl Folding {
+    pub fn fold_char(self, c: char) -> char {
+        let c = c.to_lowercase().next().unwrap_or(c);
+        if self == Folding::IgnoreAccents {
+            if let Some(base) = c.nfd().next() {
+                if matches!(base, 'a' | 'e' | 'i' | 'o' | 'u' | 'y') {
+                    return base;
+                }
+            }
+        }
+        c
+    }
+
+    /// `text` in NFC with every character folded. Folding never changes the character count
+    /// of NFC text.
+    pub fn normalize(self, text: &str) -> String {
+        text.nfc().map(|c| self.fold_char(c)).collect()
+    }
+}
+
+/// Trie of the syllables a voice can play, each node holding the recordings of the spelling
+/// leading to it. `H` is the handle type of the recordings.
+pub struct SyllablesTree<H> {
+    arena: Arena<SyllableAtom<H>>,
+    root: Option<NodeId>,
+    folding: Folding
+}
+
+impl<H> Default for SyllablesTree<H> {
+    fn default() -> Self {
+        SyllablesTree {
+            arena: Arena::new(),
+            root: None,
+            folding: Folding::Exact
+        }
+    }
+}
+
+impl<H: Clone> SyllablesTree<H> {
+    pub fn new() -> SyllablesTree<H> {
+        SyllablesTree::with_folding(Folding::Exact)
+    }
+
+    pub fn with_folding(folding: Folding) -> SyllablesTree<H> {
+        SyllablesTree {
+            arena: Arena::new(),
+            root: None,
+            folding
+        }
+    }
+
+    /// `text` as the tree compares it with its syllables.
+    pub fn normalize(&self, text: &str) -> String {
+        self.folding.normalize(text)
+    }
+
+    fn find_child(&self, node_id: NodeId, c: char) -> Option<NodeId> {
+        for child_id in node_id.children(&self.arena) {
+            if let Some(child_node) = self.arena.get(child_id) {
+                let child_char = child_node.get().char();
+                if child_char == c {
+                    return Some(child_id);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn get(&self, syllable: String) -> Option<(SyllableAudio<H>, bool)>
+    {
+        let syllable = self.folding.normalize(&syllable);
+        let mut chars = syllable.chars();
+        trace!("Looking up '{}'", syllable);
+
+        if let Some(mut last_node_id) = self.root {
+            loop {
+                match chars.next() {
+                    Some(c) => {
+                        match self.find_child(last_node_id, c) {
+                            Some(child_id) => {
+                                last_node_id = child_id;
+                            },
+                            None => { return None; }
+                        }
+                    },
+                    None => {
+                        // All syllable's letters consumed, return audio handle for the leaf node
+                        return match self.arena.get(last_node_id) {
+                            Some(node) => {
+                                if let Some(audio) = &node.get().audio {
+                                    return Some((audio.clone(), last_node_id.children(&self.arena).next().is_none()));
+                                }
+
+                                // Fall back to the last letter alone, unless that is what was asked for
+                                let last_char = node.get().char().to_string();
+                                if last_char == syllable {
+                                    return None;
+                                }
+                                return self.get(last_char).map(|r| (r.0, false));
+                            },
+                            None => None
+                        };
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Recordings of `syllable` itself, without the fallback of `get` to its last letter.
+    pub fn get_exact(&self, syllable: &str) -> Option<SyllableAudio<H>> {
+        let mut node_id = self.root?;
+        for c in self.folding.normalize(syllable).chars() {
+            node_id = self.find_child(node_id, c)?;
+        }
+        self.arena[node_id].get().audio.clone()
+    }
+
+    /// Split `text` into a sequence of known syllables.
+    ///
+    /// The longest syllable is tried first at each position, backtracking to shorter ones when
+    /// the rest of the text cannot be split. Returns `None` if no complete split exists.
+    ///
+    /// Syllables are matched on the folded text but keep the characters of `text`, in NFC.
+    pub fn segment(&self, text: &str) -> Option<Vec<Segment<H>>> {
+        let chars: Vec<char> = text.nfc().collect();
+        let folded_chars: Vec<char> = chars.iter().map(|c| self.folding.fold_char(*c)).collect();
+        let mut dead_ends = HashSet::new();
+        let mut segments = Vec::new();
+
+        if self.segment_recursive(&chars, &folded_chars, 0, &mut dead_ends, &mut segments) {
+            Some(segments)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Nodes carrying an audio handle along the path spelled by the start of `chars`, shortest first.
+    fn prefix_matches(&self, chars: &[char]) -> Vec<(usize, NodeId)> {
+        let mut matches = Vec::new();
+
+        if let Some(mut last_node_id) = self.root {
+            for (i, c) in chars.iter().enumerate() {
+                match self.find_child(last_node_id, *c) {
+                    Some(child_id) => {
+                        if self.arena[child_id].get().audio.is_some() {
+                            matches.push((i + 1, child_id));
+                        }
+                        last_node_id = child_id;
+                    },
+                    None => break
+                }
+            }
+        }
+
+        matches
+    }
+
+    fn segment_recursive(&self, chars: &[char], folded_chars: &[char], start: usize, dead_ends: &mut HashSet<usize>, segments: &mut Vec<Segment<H>>) -> bool
+    {
+        if start == chars.len() {
+            return true;
+        }
+
+        if dead_ends.contains(&start) {
+            return false;
+        }
+
+        for (len, node_id) in self.prefix_matches(&folded_chars[start..]).into_iter().rev() {
+            if let Some(audio) = &self.arena[node_id].get().audio {
+                segments.push(Segment {
+                    syllable: chars[start..start + len].iter().collect(),
+                    audio: audio.clone(),
+                    is_leaf: node_id.children(&self.arena).next().is_none()
+                });
+
+                if self.segment_recursive(chars, folded_chars, start + len, dead_ends, segments) {
+                    return true;
+                }
+
+                segments.pop();
+            }
+        }
+
+        dead_ends.insert(start);
+        false
+    }
+
+    /// Walk down from `root_id` along `chars`, creating the missing nodes, and return the last one.
+    fn add_syllable_recursive(&mut self, chars: &mut Chars, root_id: NodeId) -> NodeId
+    {
+        let mut new_root_id_opt: Option<NodeId> = None;
+        if let Some(c) = chars.next() {
+            for child_id in root_id.children(&self.arena) {
+                if let Some(child_node) = self.arena.get(child_id) {
+                    let child_char = child_node.get().char();
+                    if child_char == c {
+                        new_root_id_opt = Some(child_id);
+                        break;
+                    }
+                    else if child_char > c {
+                        let new_root_id = self.arena.new_node(SyllableAtom::new(c));
+                        new_root_id_opt = Some(new_root_id);
+                        child_id.insert_before(new_root_id, &mut self.arena);
+                        break;
+                    }
+                }
+            }
+
+
+            if let Some(child_id) = new_root_id_opt {
+                self.add_syllable_recursive(chars, child_id)
+            }
+            else {
+                let new_root_id = self.arena.new_node(SyllableAtom::new(c));
+                root_id.append(new_root_id, &mut self.arena);
+                self.add_syllable_recursive(chars, new_root_id)
+            }
+        }
+        else {
+            root_id
+        }
+    }
+
+    fn syllable_node(&mut self, syllable: &str) -> NodeId {
+        let root_id = match self.root {
+            Some(root_id) => root_id,
+            None => {
+                let root_id = self.arena.new_node(SyllableAtom::new(ROOT_CHAR));
+                self.root = Some(root_id);
+                root_id
+            }
+        };
+
+        self.add_syllable_recursive(&mut syllable.chars(), root_id)
+    }
+
+    /// Register `clip` as a recording of `syllable`. Adding several clips to the same syllable
+    /// keeps all of them as alternative takes.
+    ///
+    /// When folding makes two spellings equal, such as "é" and "e" with
+    /// `Folding::IgnoreAccents`, the recording of the unchanged spelling wins.
+    pub fn add_syllable(&mut self, syllable: &str, clip: AudioClip<H>) {
+        let normalized = self.folding.normalize(syllable);
+        if normalized.is_empty() {
+            return;
+        }
+
+        let is_folded = normalized != Folding::Exact.normalize(syllable);
+        let node_id = self.syllable_node(&normalized);
+        let atom = self.arena[node_id].get_mut();
+
+        match (atom.audio.is_some(), atom.folded_audio, is_folded) {
+            (true, false, true) => {},
+            (true, true, false) => {
+                atom.audio = None;
+                atom.folded_audio = false;
+                atom.add_clip(clip);
+            },
+            _ => {
+                atom.folded_audio = is_folded;
+                atom.add_clip(clip);
+            }
+        }
+    }
+
+    /// Every syllable having audio, with its recordings, in alphabetical order.
+    pub fn syllables(&self) -> Vec<(String, SyllableAudio<H>)> {
+        let mut syllables = Vec::new();
+        self.for_each_prefix(|prefix, atom| {
+            if let Some(audio) = &atom.audio {
+                syllables.push((prefix.to_string(), audio.clone()));
+            }
+        });
+        syllables
+    }
+
+    /// Beginnings of syllables that have no audio of their own, in alphabetical order.
+    pub fn silent_prefixes(&self) -> Vec<String> {
+        let mut prefixes = Vec::new();
+        self.for_each_prefix(|prefix, atom| {
+            if atom.audio.is_none() {
+                prefixes.push(prefix.to_string());
+            }
+        });
+        prefixes
+    }
+
+    /// Call `f` with the spelling and atom of every node but the root, depth first.
+    fn for_each_prefix<F: FnMut(&str, &SyllableAtom<H>)>(&self, mut f: F) {
+        if let Some(root_id) = self.root {
+            let mut prefix = String::new();
+            self.for_each_prefix_recursive(root_id, &mut prefix, &mut f);
+        }
+    }
+
+    fn for_each_prefix_recursive<F: FnMut(&str, &SyllableAtom<H>)>(&self, node_id: NodeId, prefix: &mut String, f: &mut F) {
+        for child_id in node_id.children(&self.arena) {
+            let atom = self.arena[child_id].get();
+            prefix.push(atom.char());
+
+            f(prefix, atom);
+            self.for_each_prefix_recursive(child_id, prefix, f);
+
+            prefix.pop();
+        }
+    }
+
+    /// Add the syllables of `fallback` that this tree has no audio for.
+    pub fn merge_fallback(&mut self, fallback: &SyllablesTree<H>) {
+        for (syllable, audio) in fallback.syllables() {
+            let node_id = self.syllable_node(&syllable);
+            let atom = self.arena[node_id].get_mut();
+            if atom.audio.is_none() {
+                atom.audio = Some(audio);
+            }
+        }
+    }
+}
+
+impl<H> fmt::Display for SyllablesTree<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut indent_level = 0;
+        if let Some(root_node_id) = self.root {
+            for node_edge in root_node_id.traverse(&self.arena) {
+                match node_edge {
+                    NodeEdge::Start(node_id) => {
+                        if indent_level > 1 {
+                            write!(f, "{}|---", " ".repeat(4 * (indent_level - 2)))?;
+                        }
+                        if indent_level > 0 {
+                            if let Some(node) = self.arena.get(node_id) {
+                                write!(f, "{}{}", node.get().char(), if node.get().audio.is_some() { "[x]" } else { "" })?;
+                            }
+                            writeln!(f)?;
+                        }
+                        indent_level += 1;
+                    },
+                    NodeEdge::End(_) => {
+                        indent_level -= 1;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// A tree where the clip of each syllable is its index in `syllables`.
+    fn tree_of(syllables: &[&str]) -> SyllablesTree<usize> {
+        let mut tree = SyllablesTree::new();
+        for (i, syllable) in syllables.iter().enumerate() {
+            tree.add_syllable(syllable, AudioClip::new(i));
+        }
+        tree
+    }
+
+    fn handles(audio: &SyllableAudio<usize>) -> Vec<usize> {
+        audio.clips.iter().map(|clip| clip.handle).collect()
+    }
+
+    fn get(tree: &SyllablesTree<usize>, syllable: &str) -> Option<(Vec<usize>, bool)> {
+        tree.get(syllable.to_string()).map(|(audio, is_leaf)| (handles(&audio), is_leaf))
+    }
+
+    fn children_are_sorted(tree: &SyllablesTree<usize>) -> bool {
+        tree.arena.iter()
+            .filter(|node| !node.is_removed())
+            .filter_map(|node| tree.arena.get_node_id(node))
+            .all(|node_id| {
+                let chars: Vec<char> = node_id.children(&tree.arena).map(|child_id| tree.arena[child_id].get().char()).collect();
+                chars.windows(2).all(|pair| pair[0] < pair[1])
+            })
+    }
+
+    #[test]
+    fn syllables_come_out_in_alphabetical_order() {
+        let tree = tree_of(&["to", "ba", "da", "b", "bra", "a"]);
+        let syllables: Vec<String> = tree.syllables().into_iter().map(|(syllable, _)| syllable).collect();
+
+        assert_eq!(syllables, ["a", "b", "ba", "bra", "da", "to"]);
+        assert!(children_are_sorted(&tree));
+    }
+
+    #[test]
+    fn insertion_before_every_existing_child() {
+        let tree = tree_of(&["zo", "mo", "fo", "ba"]);
+        let root_id = tree.root.unwrap();
+        let chars: Vec<char> = root_id.children(&tree.arena).map(|child_id| tree.arena[child_id].get().char()).collect();
+
+        assert_eq!(chars, ['b', 'f', 'm', 'z']);
+    }
+
+    #[test]
+    fn get_tells_leaves_from_prefixes() {
+        let tree = tree_of(&["ch", "cha", "chat"]);
+
+        assert_eq!(get(&tree, "ch"), Some((vec![0], false)));
+        assert_eq!(get(&tree, "cha"), Some((vec![1], false)));
+        assert_eq!(get(&tree, "chat"), Some((vec![2], true)));
+    }
+
+    #[test]
+    fn get_unknown_syllable() {
+        let tree = tree_of(&["ba"]);
+
+        assert_eq!(get(&tree, "bo"), None);
+        assert_eq!(get(&tree, "bat"), None);
+        assert_eq!(get(&SyllablesTree::new(), "ba"), None);
+    }
+
+    #[test]
+    fn silent_prefix_falls_back_to_its_last_letter() {
+        let tree = tree_of(&["tra", "r"]);
+
+        assert_eq!(get(&tree, "tr"), Some((vec![1], false)));
+        assert_eq!(tree.silent_prefixes(), ["t", "tr"]);
+    }
+
+    #[test]
+    fn get_exact_has_no_fallback() {
+        let tree = tree_of(&["tra", "r"]);
+
+        assert_eq!(tree.get_exact("tr").map(|audio| handles(&audio)), None);
+        assert_eq!(tree.get_exact("tra").map(|audio| handles(&audio)), Some(vec![0]));
+        assert_eq!(tree.get_exact("r").map(|audio| handles(&audio)), Some(vec![1]));
+        assert_eq!(tree.get_exact("").map(|audio| handles(&audio)), None);
+    }
+
+    #[test]
+    fn silent_prefix_without_fallback() {
+        let tree = tree_of(&["tra", "ra"]);
+
+        assert_eq!(get(&tree, "t"), None);
+        assert_eq!(get(&tree, "tr"), None);
+        assert_eq!(get(&tree, "r"), None);
+    }
+
+    #[test]
+    fn several_takes_of_a_syllable() {
+        let tree = tree_of(&["o", "ba", "o"]);
+
+        assert_eq!(get(&tree, "o"), Some((vec![0, 2], true)));
+    }
+
+    #[test]
+    fn unicode_spellings_are_normalized() {
+        let tree = tree_of(&["é", "Ça", "ou"]);
+
+        assert_eq!(get(&tree, "e\u{301}"), Some((vec![0], true)));
+        assert_eq!(get(&tree, "ça"), Some((vec![1], true)));
+        assert_eq!(get(&tree, "OU"), Some((vec![2], true)));
+        assert_eq!(get(&tree, "e"), None);
+    }
+
+    #[test]
+    fn ignore_accents_prefers_the_plain_spelling() {
+        let mut tree = SyllablesTree::with_folding(Folding::IgnoreAccents);
+        tree.add_syllable("é", AudioClip::new(0));
+        tree.add_syllable("e", AudioClip::new(1));
+        tree.add_syllable("è", AudioClip::new(2));
+
+        assert_eq!(get(&tree, "é"), Some((vec![1], true)));
+        assert_eq!(get(&tree, "e"), Some((vec![1], true)));
+    }
+
+    #[test]
+    fn segment_backtracks_to_shorter_syllables() {
+        let tree = tree_of(&["ba", "bat", "to", "teau"]);
+        let segments: Vec<(String, bool)> = tree.segment("Bateau").unwrap().into_iter().map(|segment| (segment.syllable, segment.is_leaf)).collect();
+
+        assert_eq!(segments, [("Ba".to_string(), false), ("teau".to_string(), true)]);
+        assert!(tree.segment("bati").is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn every_syllable_is_found(syllables in prop::collection::vec("[a-dé]{1,4}", 1..20)) {
+            let syllables: Vec<&str> = syllables.iter().map(String::as_str).collect();
+            let tree = tree_of(&syllables);
+
+            for (i, syllable) in syllables.iter().enumerate() {
+                let (handles, is_leaf) = get(&tree, syllable).unwrap();
+                let has_longer = syllables.iter().any(|other| other.len() > syllable.len() && other.starts_with(syllable));
+
+                prop_assert!(handles.contains(&i));
+                prop_assert_eq!(is_leaf, !has_longer);
+            }
+        }
+
+        #[test]
+        fn children_stay_sorted(syllables in prop::collection::vec("[a-zàéô]{1,5}", 0..30)) {
+            let syllables: Vec<&str> = syllables.iter().map(String::as_str).collect();
+            let tree = tree_of(&syllables);
+
+            prop_assert!(children_are_sorted(&tree));
+        }
+
+        #[test]
+        fn syllables_lists_each_spelling_once(syllables in prop::collection::vec("[a-e]{1,3}", 0..20)) {
+            let syllables: Vec<&str> = syllables.iter().map(String::as_str).collect();
+            let tree = tree_of(&syllables);
+            let mut expected: Vec<&str> = syllables.clone();
+            expected.sort_unstable();
+            expected.dedup();
+
+            let listed: Vec<String> = tree.syllables().into_iter().map(|(syllable, _)| syllable).collect();
+            prop_assert_eq!(listed, expected);
+        }
+    }
+}
@@ -0,0 +1,389 @@
+#[cfg(feature = "bevy")]
+use bevy::prelude::{AssetServer, Handle, AudioSource};
+use log::{info, warn};
+use serde::Deserialize;
+
+use core::fmt;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{AudioClip, Folding, SyllablesTree};
+use crate::source::FileSource;
+
+/// Name of the manifest expected at the root of every voice directory.
+pub const MANIFEST_FILE: &str = "voice.toml";
+
+/// Asset folder holding one directory per voice.
+pub const VOICES_FOLDER: &str = "audio";
+
+/// Content of a `voice.toml` manifest.
+///
+/// ```toml
+/// [voice]
+/// language = "fr"
+/// speaker = "Default"
+/// gain = 1.0
+/// fallback = ["other-voice"]
+///
+/// [audio]
+/// "ba.wav" = "ba"
+/// "o.wav" = ["o", "au", "eau"]
+/// "o-loud.wav" = { spellings = ["o"], gain = 0.5 }
+/// ```
+///
+/// Keys of the `[audio]` table are paths relative to the voice directory. A spelling listed
+/// for several files gets all of them as alternative takes.
+#[derive(Deserialize)]
+pub struct VoiceManifest {
+    pub voice: VoiceInfo,
+    #[serde(default)]
+    pub audio: BTreeMap<String, AudioEntry>
+}
+
+#[derive(Deserialize)]
+pub struct VoiceInfo {
+    pub language: String,
+    pub speaker: String,
+    /// Volume applied to every file of the voice that does not set its own.
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+    /// Voices looked up, in order, for the syllables this one has no recording of.
+    #[serde(default)]
+    pub fallback: Vec<String>
+}
+
+fn default_gain() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum AudioEntry {
+    Spelling(String),
+    Spellings(Vec<String>),
+    Detailed {
+        spellings: Vec<String>,
+        gain: Option<f32>
+    }
+}
+
+impl AudioEntry {
+    pub fn spellings(&self) -> Vec<&str> {
+        match self {
+            AudioEntry::Spelling(spelling) => vec![spelling.as_str()],
+            AudioEntry::Spellings(spellings) | AudioEntry::Detailed { spellings, .. } => {
+                spellings.iter().map(String::as_str).collect()
+            }
+        }
+    }
+
+    pub fn gain(&self) -> Option<f32> {
+        match self {
+            AudioEntry::Detailed { gain, .. } => *gain,
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum VoiceError {
+    Io(io::Error),
+    NotADirectory(PathBuf),
+    Manifest(toml::de::Error)
+}
+
+impl VoiceError {
+    /// Whether the file or directory read does not exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, VoiceError::Io(err) if err.kind() == io::ErrorKind::NotFound)
+    }
+}
+
+impl fmt::Display for VoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoiceError::Io(err) => write!(f, "{}", err),
+            VoiceError::NotADirectory(path) => write!(f, "not a directory: {}", path.display()),
+            VoiceError::Manifest(err) => write!(f, "invalid {}: {}", MANIFEST_FILE, err)
+        }
+    }
+}
+
+impl From<io::Error> for VoiceError {
+    fn from(err: io::Error) -> Self {
+        VoiceError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for VoiceError {
+    fn from(err: toml::de::Error) -> Self {
+        VoiceError::Manifest(err)
+    }
+}
+
+/// Turns the path of a recording, relative to the assets, into the handle `H` stored in the
+/// syllables tree.
+pub trait AudioLoader<H> {
+    fn load_audio(&self, path: &Path) -> H;
+}
+
+#[cfg(feature = "bevy")]
+impl AudioLoader<Handle<AudioSource>> for AssetServer {
+    fn load_audio(&self, path: &Path) -> Handle<AudioSource> {
+        self.load(path)
+    }
+}
+
+impl<H, F: Fn(&Path) -> H> AudioLoader<H> for F {
+    fn load_audio(&self, path: &Path) -> H {
+        self(path)
+    }
+}
+
+/// Read and parse the manifest of the voice directory `path`.
+pub fn read_manifest<P: AsRef<Path>>(path: P, source: &(impl FileSource + ?Sized)) -> Result<VoiceManifest, VoiceError> {
+    let bytes = source.read(&path.as_ref().join(MANIFEST_FILE))?;
+
+    Ok(toml::from_slice(&bytes)?)
+}
+
+/// Build the syllables tree of the voice directory `path` from its manifest, comparing
+/// spellings with `folding`.
+///
+/// Directories without a manifest fall back to `load_folder`, mapping each file stem to a
+/// syllable.
+pub fn load_voice<H: Clone, P: AsRef<Path>>(
+    path: P,
+    source: &(impl FileSource + ?Sized),
+    loader: &impl AudioLoader<H>,
+    folding: Folding
+) -> Result<SyllablesTree<H>, VoiceError> {
+    let path = path.as_ref();
+    let mut syllabs_tree = SyllablesTree::with_folding(folding);
+
+    match read_manifest(path, source) {
+        Ok(manifest) => {
+            info!("Loading voice of {} ({}) from '{}'", manifest.voice.speaker, manifest.voice.language, path.display());
+
+            for (file, entry) in manifest.audio.iter() {
+                let clip = AudioClip {
+                    handle: loader.load_audio(&path.join(file)),
+                    volume: entry.gain().unwrap_or(manifest.voice.gain)
+                };
+
+                for spelling in entry.spellings() {
+                    syllabs_tree.add_syllable(spelling, clip.clone());
+                }
+            }
+        },
+        Err(err) if err.is_not_found() => {
+            warn!("No {} in '{}', using file names as syllables", MANIFEST_FILE, path.display());
+
+            for (file_stem, audio_handle) in load_folder(path, source, loader)?.into_iter() {
+                syllabs_tree.add_syllable(&file_stem, AudioClip::new(audio_handle));
+            }
+        },
+        Err(err) => return Err(err)
+    }
+
+    Ok(syllabs_tree)
+}
+
+/// A voice directory found by `VoicePacks::discover`.
+pub struct VoicePack {
+    /// Name of the directory, used to select the voice.
+    pub name: String,
+    pub path: PathBuf,
+    pub language: Option<String>,
+    pub speaker: Option<String>,
+    pub fallback: Vec<String>
+}
+
+impl VoicePack {
+    /// Human readable name, the speaker when the manifest gives one.
+    pub fn label(&self) -> String {
+        match (&self.speaker, &self.language) {
+            (Some(speaker), Some(language)) => format!("{} ({})", speaker, language),
+            (Some(speaker), None) => speaker.clone(),
+            _ => self.name.clone()
+        }
+    }
+}
+
+/// Registry of the voices available under `VOICES_FOLDER`, sorted by name.
+#[derive(Default)]
+pub struct VoicePacks {
+    pub packs: Vec<VoicePack>
+}
+
+impl VoicePacks {
+    pub fn discover<P: AsRef<Path>>(path: P, source: &(impl FileSource + ?Sized)) -> Result<VoicePacks, VoiceError> {
+        let path = path.as_ref();
+        if !source.is_dir(path) {
+            return Err(VoiceError::NotADirectory(path.to_path_buf()));
+        }
+
+        let mut packs = Vec::new();
+        for child_path in source.read_dir(path)? {
+            if !source.is_dir(&child_path) {
+                continue;
+            }
+
+            let name = match child_path.file_name().and_then(|ostr| ostr.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue
+            };
+
+            let (language, speaker, fallback) = match read_manifest(&child_path, source) {
+                Ok(manifest) => (Some(manifest.voice.language), Some(manifest.voice.speaker), manifest.voice.fallback),
+                Err(err) if err.is_not_found() => (None, None, Vec::new()),
+                Err(err) => {
+                    warn!("Skipping voice '{}': {}", name, err);
+                    continue;
+                }
+            };
+
+            packs.push(VoicePack {
+                name,
+                path: child_path,
+                language,
+                speaker,
+                fallback
+            });
+        }
+
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(VoicePacks { packs })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&VoicePack> {
+        self.packs.iter().find(|pack| pack.name == name)
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.packs.iter().position(|pack| pack.name == name)
+    }
+
+    /// The voice `name` followed by its fallbacks, depth first, in lookup order.
+    pub fn chain(&self, name: &str) -> Vec<&VoicePack> {
+        let mut chain = Vec::new();
+        self.chain_recursive(name, &mut chain);
+        chain
+    }
+
+    fn chain_recursive<'a>(&'a self, name: &str, chain: &mut Vec<&'a VoicePack>) {
+        if chain.iter().any(|pack| pack.name == name) {
+            return;
+        }
+
+        match self.get(name) {
+            Some(voice_pack) => {
+                chain.push(voice_pack);
+                for fallback in voice_pack.fallback.iter() {
+                    self.chain_recursive(fallback, chain);
+                }
+            },
+            None => warn!("Unknown voice '{}'", name)
+        }
+    }
+}
+
+/// Build a single syllables tree from a chain of voices: each syllable is played by the first
+/// voice of the chain that has a recording of it.
+pub fn load_voice_chain<H: Clone>(
+    chain: &[&VoicePack],
+    source: &(impl FileSource + ?Sized),
+    loader: &impl AudioLoader<H>,
+    folding: Folding
+) -> Result<SyllablesTree<H>, VoiceError> {
+    let mut voices = chain.iter();
+    let mut syllabs_tree = match voices.next() {
+        Some(voice_pack) => load_voice(&voice_pack.path, source, loader, folding)?,
+        None => return Ok(SyllablesTree::with_folding(folding))
+    };
+
+    for voice_pack in voices {
+        match load_voice(&voice_pack.path, source, loader, folding) {
+            Ok(fallback_tree) => syllabs_tree.merge_fallback(&fallback_tree),
+            Err(err) => warn!("Skipping fallback voice '{}': {}", voice_pack.name, err)
+        }
+    }
+
+    Ok(syllabs_tree)
+}
+
+/// Every `.wav` file under `path`, recursively, with its file stem.
+pub fn find_audio_files<P: AsRef<Path>>(
+    path: P,
+    source: &(impl FileSource + ?Sized),
+) -> Result<Vec<(String, PathBuf)>, VoiceError> {
+    let path = path.as_ref();
+    if !source.is_dir(path) {
+        return Err(VoiceError::NotADirectory(path.to_path_buf()));
+    }
+
+    let mut files = Vec::new();
+    for child_path in source.read_dir(path)? {
+        if source.is_dir(&child_path) {
+            files.extend(find_audio_files(&child_path, source)?);
+        } else {
+            let ext = child_path.extension();
+            if ext.and_then(|ostr| ostr.to_str()) != Some("wav") {
+                continue;
+            }
+
+            if let Some(file_stem) = child_path.file_stem().and_then(|ostr| ostr.to_str()) {
+                files.push((file_stem.to_string(), child_path.clone()));
+            }
+       }
+    }
+
+    Ok(files)
+}
+
+/// One handle per lowercase file stem for the `.wav` files under `path`.
+pub fn load_folder<H, P: AsRef<Path>>(
+    path: P,
+    source: &(impl FileSource + ?Sized),
+    loader: &impl AudioLoader<H>,
+) -> Result<HashMap<String, H>, VoiceError> {
+    let mut handles = HashMap::new();
+    for (file_stem, child_path) in find_audio_files(path, source)? {
+        let lowercase = file_stem.to_lowercase();
+        handles.entry(lowercase).or_insert_with(|| loader.load_audio(&child_path));
+    }
+
+    Ok(handles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Directory;
+
+    use std::fs;
+
+    #[test]
+    fn voices_are_read_from_a_directory() {
+        let root = std::env::temp_dir().join(format!("syllabs-voice-{}", std::process::id()));
+        fs::create_dir_all(root.join("main")).unwrap();
+        fs::create_dir_all(root.join("plain")).unwrap();
+        fs::write(root.join("main").join(MANIFEST_FILE), "[voice]\nlanguage = \"fr\"\nspeaker = \"Main\"\nfallback = [\"plain\"]\n\n[audio]\n\"o.wav\" = [\"o\", \"au\"]\n").unwrap();
+        fs::write(root.join("plain").join("Ba.wav"), b"").unwrap();
+        fs::write(root.join("plain").join("notes.txt"), b"").unwrap();
+
+        let source = Directory(root.clone());
+        let voice_packs = VoicePacks::discover("", &source).unwrap();
+        let loader = |path: &Path| path.to_path_buf();
+        let syllabs_tree = load_voice_chain(&voice_packs.chain("main"), &source, &loader, Folding::default()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(voice_packs.get("main").map(VoicePack::label).as_deref(), Some("Main (fr)"));
+        assert_eq!(voice_packs.get("plain").map(VoicePack::label).as_deref(), Some("plain"));
+        let handle = |syllable: &str| syllabs_tree.get_exact(syllable).map(|audio| audio.pick().handle.clone());
+        assert_eq!(handle("au"), Some(Path::new("main").join("o.wav")));
+        assert_eq!(handle("ba"), Some(Path::new("plain").join("Ba.wav")));
+    }
+}
@@ -0,0 +1,230 @@
+use core::fmt;
+
+/// Format codes of the `fmt ` chunk that the audio decoder can play.
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WavFormat {
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16
+}
+
+/// A RIFF/WAVE file split into its format and its raw samples.
+pub struct Wav<'a> {
+    pub format: WavFormat,
+    /// Content of the `fmt ` chunk, kept as is when writing the samples back.
+    pub format_chunk: &'a [u8],
+    pub data: &'a [u8]
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WavError {
+    NotRiff,
+    NotWave,
+    Truncated,
+    MissingFormat,
+    MissingData,
+    UnsupportedFormat(u16),
+    InvalidFormat,
+    MixedFormats
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::NotRiff => write!(f, "not a RIFF file"),
+            WavError::NotWave => write!(f, "not a WAVE file"),
+            WavError::Truncated => write!(f, "file is truncated"),
+            WavError::MissingFormat => write!(f, "no 'fmt ' chunk"),
+            WavError::MissingData => write!(f, "no 'data' chunk"),
+            WavError::UnsupportedFormat(format_tag) => write!(f, "unsupported sample format {:#06x}", format_tag),
+            WavError::InvalidFormat => write!(f, "invalid channel count, sample rate or sample size"),
+            WavError::MixedFormats => write!(f, "recordings have different formats")
+        }
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+impl<'a> Wav<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Wav<'a>, WavError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" {
+            return Err(WavError::NotRiff);
+        }
+        if &bytes[8..12] != b"WAVE" {
+            return Err(WavError::NotWave);
+        }
+
+        let mut format = None;
+        let mut format_chunk = None;
+        let mut data = None;
+        let mut offset = 12;
+
+        while offset + 8 <= bytes.len() {
+            let chunk_id = &bytes[offset..offset + 4];
+            let chunk_size = read_u32(bytes, offset + 4) as usize;
+            let chunk_start = offset + 8;
+            let chunk_end = chunk_start.checked_add(chunk_size).ok_or(WavError::Truncated)?;
+            if chunk_end > bytes.len() {
+                return Err(WavError::Truncated);
+            }
+
+            match chunk_id {
+                b"fmt " => {
+                    if chunk_size < 16 {
+                        return Err(WavError::Truncated);
+                    }
+                    format = Some(WavFormat {
+                        format_tag: read_u16(bytes, chunk_start),
+                        channels: read_u16(bytes, chunk_start + 2),
+                        sample_rate: read_u32(bytes, chunk_start + 4),
+                        bits_per_sample: read_u16(bytes, chunk_start + 14)
+                    });
+                    format_chunk = Some(&bytes[chunk_start..chunk_end]);
+                },
+                b"data" => data = Some(&bytes[chunk_start..chunk_end]),
+                _ => {}
+            }
+
+            // Chunks are padded to an even size
+            offset = chunk_end + chunk_size % 2;
+        }
+
+        let format = format.ok_or(WavError::MissingFormat)?;
+        let format_chunk = format_chunk.ok_or(WavError::MissingFormat)?;
+        let data = data.ok_or(WavError::MissingData)?;
+
+        match format.format_tag {
+            WAVE_FORMAT_PCM | WAVE_FORMAT_IEEE_FLOAT | WAVE_FORMAT_EXTENSIBLE => {},
+            format_tag => return Err(WavError::UnsupportedFormat(format_tag))
+        }
+
+        if format.channels == 0 || format.sample_rate == 0 || format.bits_per_sample == 0 || format.bits_per_sample % 8 != 0 {
+            return Err(WavError::InvalidFormat);
+        }
+
+        Ok(Wav { format, format_chunk, data })
+    }
+
+    /// Whether the samples are floating point numbers rather than integers.
+    fn is_float(&self) -> bool {
+        match self.format.format_tag {
+            WAVE_FORMAT_IEEE_FLOAT => true,
+            // The sub-format of an extensible format starts with the format code
+            WAVE_FORMAT_EXTENSIBLE => self.format_chunk.len() >= 26 && read_u16(self.format_chunk, 24) == WAVE_FORMAT_IEEE_FLOAT,
+            _ => false
+        }
+    }
+
+    /// The samples multiplied by `gain`, clipped to the range of their format.
+    pub fn amplify(&self, gain: f32) -> Result<Vec<u8>, WavError> {
+        let size = usize::from(self.format.bits_per_sample / 8);
+        let mut data = self.data.to_vec();
+        for sample in data.chunks_exact_mut(size) {
+            match (self.is_float(), size) {
+                (true, 4) => {
+                    let value = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]) * gain;
+                    sample.copy_from_slice(&value.clamp(-1.0, 1.0).to_le_bytes());
+                },
+
+                // 8-bit samples are unsigned, centred on 128
+                (false, 1) => sample[0] = ((f32::from(sample[0]) - 128.0) * gain + 128.0).round().clamp(0.0, 255.0) as u8,
+                (false, 2..=4) => {
+                    // Sign-extend the little-endian integer from its top byte
+                    let mut bytes = [0; 4];
+                    bytes[4 - size..].copy_from_slice(sample);
+                    let value = i32::from_le_bytes(bytes) >> (8 * (4 - size));
+                    let max = (1i64 << (8 * size - 1)) - 1;
+                    let amplified = (value as f64 * f64::from(gain)).round().clamp(-(max as f64) - 1.0, max as f64) as i32;
+                    sample.copy_from_slice(&amplified.to_le_bytes()[..size]);
+                },
+                _ => return Err(WavError::InvalidFormat)
+            }
+        }
+        Ok(data)
+    }
+
+    /// Join the samples of `wavs`, one after the other, into a single RIFF/WAVE file. They
+    /// must all have the same format.
+    pub fn concat(wavs: &[Wav<'_>]) -> Result<Vec<u8>, WavError> {
+        let first = wavs.first().ok_or(WavError::MissingData)?;
+        if wavs.iter().any(|wav| wav.format_chunk != first.format_chunk) {
+            return Err(WavError::MixedFormats);
+        }
+
+        let format_size = first.format_chunk.len();
+        let data_size: usize = wavs.iter().map(|wav| wav.data.len()).sum();
+        let riff_size = 4 + 8 + format_size + format_size % 2 + 8 + data_size + data_size % 2;
+
+        let mut bytes = Vec::with_capacity(8 + riff_size);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(riff_size as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&(format_size as u32).to_le_bytes());
+        bytes.extend_from_slice(first.format_chunk);
+        if format_size % 2 == 1 {
+            bytes.push(0);
+        }
+
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data_size as u32).to_le_bytes());
+        for wav in wavs.iter() {
+            bytes.extend_from_slice(wav.data);
+        }
+        if data_size % 2 == 1 {
+            bytes.push(0);
+        }
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav<'a>(format_tag: u16, bits_per_sample: u16, data: &'a [u8]) -> Wav<'a> {
+        Wav {
+            format: WavFormat { format_tag, channels: 1, sample_rate: 8000, bits_per_sample },
+            format_chunk: &[],
+            data
+        }
+    }
+
+    fn samples_16(data: &[u8]) -> Vec<i16> {
+        data.chunks_exact(2).map(|sample| i16::from_le_bytes([sample[0], sample[1]])).collect()
+    }
+
+    #[test]
+    fn amplify_integer_samples() {
+        let data: Vec<u8> = [100i16, -100, 20000, -20000].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+
+        assert_eq!(samples_16(&wav(WAVE_FORMAT_PCM, 16, &data).amplify(0.5).unwrap()), [50, -50, 10000, -10000]);
+        assert_eq!(samples_16(&wav(WAVE_FORMAT_PCM, 16, &data).amplify(2.0).unwrap()), [200, -200, i16::MAX, i16::MIN]);
+        assert_eq!(wav(WAVE_FORMAT_PCM, 8, &[128, 138, 118, 250]).amplify(2.0).unwrap(), [128, 148, 108, 255]);
+
+        let data_24 = [0x00, 0x00, 0xF0, 0x10, 0x00, 0x00];
+        assert_eq!(wav(WAVE_FORMAT_PCM, 24, &data_24).amplify(0.5).unwrap(), [0x00, 0x00, 0xF8, 0x08, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn amplify_float_samples() {
+        let data: Vec<u8> = [0.25f32, -0.75].iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let amplified = wav(WAVE_FORMAT_IEEE_FLOAT, 32, &data).amplify(2.0).unwrap();
+        let samples: Vec<f32> = amplified.chunks_exact(4).map(|sample| f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]])).collect();
+
+        assert_eq!(samples, [0.5, -1.0]);
+    }
+}
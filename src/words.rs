@@ -0,0 +1,98 @@
+use bevy::asset::{AssetIo, AssetIoError};
+use serde::Deserialize;
+use unicode_normalization::UnicodeNormalization;
+
+use core::fmt;
+use std::path::Path;
+
+/// Asset folder holding one `<name>.toml` file per word list.
+pub const WORDS_FOLDER: &str = "words";
+
+/// Separates the syllables of a word in a word list.
+const SYLLABLE_SEPARATOR: char = '-';
+
+/// Content of a word list file.
+///
+/// ```toml
+/// words = ["to-ma-te", "lu-ne"]
+/// ```
+#[derive(Deserialize)]
+struct WordListFile {
+    words: Vec<String>
+}
+
+#[derive(Debug)]
+pub enum WordsError {
+    Io(AssetIoError),
+    Parse(toml::de::Error),
+    EmptySyllable(String)
+}
+
+impl fmt::Display for WordsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordsError::Io(err) => write!(f, "{}", err),
+            WordsError::Parse(err) => write!(f, "invalid word list: {}", err),
+            WordsError::EmptySyllable(word) => write!(f, "'{}' has an empty syllable", word)
+        }
+    }
+}
+
+impl From<AssetIoError> for WordsError {
+    fn from(err: AssetIoError) -> Self {
+        WordsError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for WordsError {
+    fn from(err: toml::de::Error) -> Self {
+        WordsError::Parse(err)
+    }
+}
+
+/// A word split into syllables, lowercase and in NFC.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Word {
+    pub syllables: Vec<String>
+}
+
+impl Word {
+    /// Parse a word written with its syllables separated by dashes, such as "to-ma-te".
+    pub fn parse(text: &str) -> Result<Word, WordsError> {
+        let syllables: Vec<String> = text.split(SYLLABLE_SEPARATOR)
+            .map(|syllable| syllable.trim().to_lowercase().nfc().collect::<String>())
+            .collect();
+
+        if syllables.iter().any(String::is_empty) {
+            return Err(WordsError::EmptySyllable(text.to_string()));
+        }
+
+        Ok(Word { syllables })
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.syllables.concat())
+    }
+}
+
+#[derive(Default)]
+pub struct WordList {
+    pub words: Vec<Word>
+}
+
+impl WordList {
+    /// Load `<WORDS_FOLDER>/<name>.toml`.
+    pub fn load(name: &str, asset_io: &dyn AssetIo) -> Result<WordList, WordsError> {
+        let path = Path::new(WORDS_FOLDER).join(format!("{}.toml", name));
+        let bytes = futures_lite::future::block_on(asset_io.load_path(&path))?;
+        let word_list_file: WordListFile = toml::from_slice(&bytes)?;
+
+        let words = word_list_file.words.iter()
+            .map(|word| Word::parse(word))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(WordList { words })
+    }
+}